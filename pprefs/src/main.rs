@@ -1,25 +1,29 @@
 // <https://github.com/torvalds/linux/blob/master/tools/power/cpupower/utils/helpers/sysfs.c>
 
-use sysfs::api::cpu;
+use std::time::Duration;
+
 use sysfs::api::cpu::cpufreq;
+use sysfs::api::{cpu, hwmon, powercap, thermal};
 
 fn main() {
     for cpu_num in 0..cpu::num_cpus().unwrap() {
         println!(
             r#"/sys/devices/system/cpu/cpufreq/policy{}:
-    affected_cpus               - {:?}
-    bios_limit                  - {:?}
-    cpuinfo_cur_freq            - {:?}
-    cpuinfo_max_freq            - {:?}
-    cpuinfo_min_freq            - {:?}
-    cpuinfo_transition_latency  - {:?}
-    related_cpus                - {:?}
-    scaling_available_governors - {:?}
-    scaling_cur_freq            - {:?}
-    scaling_driver              - {:?}
-    scaling_governor            - {:?}
-    scaling_max_freq            - {:?}
-    scaling_setspeed            - {:?}
+    affected_cpus                 - {:?}
+    bios_limit                    - {:?}
+    cpuinfo_cur_freq              - {:?}
+    cpuinfo_max_freq              - {:?}
+    cpuinfo_min_freq              - {:?}
+    cpuinfo_transition_latency    - {:?}
+    related_cpus                  - {:?}
+    scaling_available_frequencies - {:?}
+    scaling_available_governors   - {:?}
+    scaling_boost_frequencies     - {:?}
+    scaling_cur_freq              - {:?}
+    scaling_driver                - {:?}
+    scaling_governor              - {:?}
+    scaling_max_freq              - {:?}
+    scaling_setspeed              - {:?}
 "#,
             cpu_num,
             cpufreq::affected_cpus(cpu_num),
@@ -29,7 +33,9 @@ fn main() {
             cpufreq::cpuinfo_min_freq(cpu_num),
             cpufreq::cpuinfo_transition_latency(cpu_num),
             cpufreq::related_cpus(cpu_num),
+            cpufreq::scaling_available_frequencies(cpu_num),
             cpufreq::scaling_available_governors(cpu_num),
+            cpufreq::scaling_boost_frequencies(cpu_num),
             cpufreq::scaling_cur_freq(cpu_num),
             cpufreq::scaling_driver(cpu_num),
             cpufreq::scaling_governor(cpu_num),
@@ -37,4 +43,58 @@ fn main() {
             cpufreq::scaling_setspeed(cpu_num),
         )
     }
+
+    println!("/sys/class/powercap:");
+    match powercap::EnergyMeter::all()
+        .and_then(|mut meter| meter.measure(Duration::from_millis(500)))
+    {
+        Ok(readings) => {
+            for reading in readings {
+                let zone = format!("{} ({})", reading.name, reading.zone);
+                println!("    {zone:<29} - {:.2} W", reading.watts());
+            }
+        }
+        Err(e) => println!("    {e:?}"),
+    }
+
+    println!("/sys/class/thermal:");
+    match thermal::zones() {
+        Ok(zones) => {
+            for zone in zones {
+                let name = format!(
+                    "{} (thermal_zone{zone})",
+                    thermal::zone::kind(zone).unwrap_or_default()
+                );
+                match (thermal::zone::temp(zone), thermal::headroom(zone)) {
+                    (Ok(temp), Ok(Some(headroom))) => {
+                        println!("    {name:<29} - {temp} ({headroom} headroom)")
+                    }
+                    (Ok(temp), _) => println!("    {name:<29} - {temp}"),
+                    (Err(e), _) => println!("    {name:<29} - {e:?}"),
+                }
+            }
+        }
+        Err(e) => println!("    {e:?}"),
+    }
+
+    println!("/sys/class/hwmon:");
+    match hwmon::chips() {
+        Ok(chips) => {
+            for chip in chips {
+                let name = format!(
+                    "{} (hwmon{chip})",
+                    hwmon::chip::name(chip).unwrap_or_default()
+                );
+                match hwmon::readings(chip) {
+                    Ok(readings) => {
+                        for reading in readings {
+                            println!("    {name:<29} - {reading}");
+                        }
+                    }
+                    Err(e) => println!("    {name:<29} - {e:?}"),
+                }
+            }
+        }
+        Err(e) => println!("    {e:?}"),
+    }
 }
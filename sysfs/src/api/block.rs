@@ -0,0 +1,333 @@
+//! <https://www.kernel.org/doc/html/latest/block/queue-sysfs.html>
+//!
+//! Block devices are identified by their name, such as `nvme0n1` or `sda`.
+//! Only whole disks have a request queue, so the [`queue`] attributes are
+//! not available for partitions.
+use std::convert::Infallible;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::lib::{sysfs_attrs, sysfs_list, Result, SysfsError};
+
+/// An I/O scheduler.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Scheduler {
+    /// Passes requests through in the order they are submitted, which is
+    /// best for fast NVMe devices.
+    None,
+    /// Orders requests by sector and gives them a deadline, preferring
+    /// reads over writes.
+    MqDeadline,
+    /// Throttles requests to meet latency targets for reads and synchronous
+    /// writes.
+    Kyber,
+    /// The Budget Fair Queueing scheduler, which shares the bandwidth
+    /// between processes and favours interactive ones. Best for rotational
+    /// disks.
+    Bfq,
+    /// A scheduler unknown to this crate.
+    Other(String),
+}
+
+impl Scheduler {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::None => "none",
+            Self::MqDeadline => "mq-deadline",
+            Self::Kyber => "kyber",
+            Self::Bfq => "bfq",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+impl FromStr for Scheduler {
+    type Err = Infallible;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match text {
+            "none" => Self::None,
+            "mq-deadline" => Self::MqDeadline,
+            "kyber" => Self::Kyber,
+            "bfq" => Self::Bfq,
+            other => Self::Other(other.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The names of all block devices, including partitions and virtual
+/// devices such as `loop0`, `zram0` or `dm-0`.
+pub fn devices() -> Result<Vec<String>> {
+    sysfs_list("/sys/class/block")
+}
+
+/// The names of all block devices that are whole disks, that is, all
+/// devices except partitions.
+pub fn disks() -> Result<Vec<String>> {
+    sysfs_list("/sys/block")
+}
+
+/// The names of the whole disks that are backed by hardware, skipping
+/// virtual devices.
+pub fn physical_disks() -> Result<Vec<String>> {
+    disks()?
+        .into_iter()
+        .filter_map(|name| match is_virtual(&name) {
+            Ok(true) => None,
+            Ok(false) => Some(Ok(name)),
+            Err(e) => Some(Err(e)),
+        })
+        .collect()
+}
+
+/// Whether the block device is a partition of another one.
+pub fn is_partition(name: &str) -> bool {
+    Path::new(&format!("/sys/class/block/{name}/partition")).exists()
+}
+
+/// Whether the block device is not backed by hardware, such as loop
+/// devices, RAM disks and device mapper targets.
+pub fn is_virtual(name: &str) -> Result<bool> {
+    let link =
+        std::fs::read_link(format!("/sys/class/block/{name}")).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => SysfsError::MissingAttribute,
+            _ => SysfsError::from(e),
+        })?;
+    Ok(link.to_string_lossy().contains("/devices/virtual/"))
+}
+
+/// Switches the disk to `scheduler` after checking that it is one of the
+/// schedulers the kernel offers for it.
+pub fn select_scheduler(name: &str, scheduler: &Scheduler) -> Result<()> {
+    if !queue::scheduler(name)?.contains(scheduler) {
+        return Err(SysfsError::InvalidValue(format!(
+            "the I/O scheduler `{scheduler}` is not available for {name}"
+        )));
+    }
+    queue::set_scheduler(name, scheduler.clone())
+}
+
+/// Which disks a [`Rule`] applies to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Match {
+    /// Every disk.
+    Any,
+    /// Disks whose name starts with the prefix, such as `nvme` or `sd`.
+    Prefix(String),
+    /// Disks that report being rotational, or not.
+    Rotational(bool),
+}
+
+impl Match {
+    pub fn matches(&self, name: &str) -> Result<bool> {
+        match self {
+            Self::Any => Ok(true),
+            Self::Prefix(prefix) => Ok(name.starts_with(prefix.as_str())),
+            Self::Rotational(rotational) => Ok(queue::rotational(name)? == *rotational),
+        }
+    }
+}
+
+/// Values for the [`queue`] attributes of a disk. Attributes that are
+/// `None` are left unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueueSettings {
+    pub scheduler: Option<Scheduler>,
+    pub read_ahead_kb: Option<u32>,
+    pub nr_requests: Option<u32>,
+    pub wbt_lat_usec: Option<i64>,
+    pub add_random: Option<bool>,
+    pub rq_affinity: Option<u8>,
+}
+
+impl QueueSettings {
+    /// Overrides the values of `self` with the ones set in `other`.
+    pub fn merge(&mut self, other: &Self) {
+        let Self {
+            scheduler,
+            read_ahead_kb,
+            nr_requests,
+            wbt_lat_usec,
+            add_random,
+            rq_affinity,
+        } = other;
+        if scheduler.is_some() {
+            self.scheduler.clone_from(scheduler);
+        }
+        self.read_ahead_kb = read_ahead_kb.or(self.read_ahead_kb);
+        self.nr_requests = nr_requests.or(self.nr_requests);
+        self.wbt_lat_usec = wbt_lat_usec.or(self.wbt_lat_usec);
+        self.add_random = add_random.or(self.add_random);
+        self.rq_affinity = rq_affinity.or(self.rq_affinity);
+    }
+
+    /// Writes the values that are set to the disk, stopping at the first
+    /// error.
+    pub fn apply(&self, name: &str) -> Result<()> {
+        if let Some(scheduler) = &self.scheduler {
+            select_scheduler(name, scheduler)?;
+        }
+        if let Some(read_ahead_kb) = self.read_ahead_kb {
+            queue::set_read_ahead_kb(name, read_ahead_kb)?;
+        }
+        if let Some(nr_requests) = self.nr_requests {
+            queue::set_nr_requests(name, nr_requests)?;
+        }
+        if let Some(wbt_lat_usec) = self.wbt_lat_usec {
+            queue::set_wbt_lat_usec(name, wbt_lat_usec)?;
+        }
+        if let Some(add_random) = self.add_random {
+            queue::set_add_random(name, add_random)?;
+        }
+        if let Some(rq_affinity) = self.rq_affinity {
+            queue::set_rq_affinity(name, rq_affinity)?;
+        }
+        Ok(())
+    }
+}
+
+/// Settings for the disks that match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub matches: Match,
+    pub settings: QueueSettings,
+}
+
+/// Applies `rules` to every physical disk, for example giving NVMe disks
+/// the `none` scheduler and rotational ones `bfq`.
+///
+/// Every rule that matches a disk applies, in order, so later rules
+/// override the values set by earlier ones. Disks that could not be matched
+/// or configured are returned together with the error.
+pub fn apply_rules(rules: &[Rule]) -> Result<Vec<(String, SysfsError)>> {
+    let mut refused = Vec::new();
+    for name in physical_disks()? {
+        let result = settings_for(rules, |matches| matches.matches(&name))
+            .and_then(|settings| settings.apply(&name));
+        if let Err(e) = result {
+            refused.push((name, e));
+        }
+    }
+    Ok(refused)
+}
+
+fn settings_for(
+    rules: &[Rule],
+    mut matches: impl FnMut(&Match) -> Result<bool>,
+) -> Result<QueueSettings> {
+    let mut settings = QueueSettings::default();
+    for rule in rules {
+        if matches(&rule.matches)? {
+            settings.merge(&rule.settings);
+        }
+    }
+    Ok(settings)
+}
+
+/// The request queue of a whole disk.
+#[sysfs_attrs(in "/sys/block/{name}/queue")]
+pub mod queue {
+    use super::Scheduler;
+    use crate::lib::{sysfs, Choice};
+
+    /// The current I/O scheduler, and the ones available for this disk.
+    /// Use [`super::select_scheduler`] to check the scheduler before
+    /// switching.
+    #[sysfs]
+    pub fn scheduler(name: &str) -> Choice<Scheduler> {
+        ..
+    }
+
+    /// The maximum number of kilobytes to read ahead.
+    #[sysfs]
+    pub fn read_ahead_kb(name: &str) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |kb: u32| format!("{kb}");
+        ..
+    }
+
+    /// The number of requests that may be allocated in the block layer for
+    /// reads or writes each.
+    #[sysfs]
+    pub fn nr_requests(name: &str) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |requests: u32| format!("{requests}");
+        ..
+    }
+
+    /// Whether the disk is rotational, as opposed to solid state. The
+    /// kernel may guess wrong, for example for disks behind USB bridges, in
+    /// which case this can be overridden.
+    #[sysfs]
+    pub fn rotational(name: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |rotational: bool| format!("{}", rotational as u8);
+        ..
+    }
+
+    /// The target latency for writeback throttling in microseconds. `0`
+    /// disables throttling, and writing `-1` restores the default.
+    #[sysfs]
+    pub fn wbt_lat_usec(name: &str) -> i64 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |usec: i64| format!("{usec}");
+        ..
+    }
+
+    /// Whether the disk contributes to the entropy pool.
+    #[sysfs]
+    pub fn add_random(name: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |add_random: bool| format!("{}", add_random as u8);
+        ..
+    }
+
+    /// Where request completions are processed. With `0` anywhere, with
+    /// `1` in the group of CPUs sharing a cache with the submitting CPU,
+    /// and with `2` on the submitting CPU itself.
+    #[sysfs]
+    pub fn rq_affinity(name: &str) -> u8 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |affinity: u8| format!("{affinity}");
+        ..
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_rules_override_earlier_ones() {
+        let rules = [
+            Rule {
+                matches: Match::Any,
+                settings: QueueSettings {
+                    scheduler: Some(Scheduler::MqDeadline),
+                    read_ahead_kb: Some(128),
+                    ..Default::default()
+                },
+            },
+            Rule {
+                matches: Match::Prefix("nvme".to_owned()),
+                settings: QueueSettings {
+                    scheduler: Some(Scheduler::None),
+                    ..Default::default()
+                },
+            },
+        ];
+        let nvme = settings_for(&rules, |matches| Ok(matches != &Match::Rotational(true))).unwrap();
+        assert_eq!(nvme.scheduler, Some(Scheduler::None));
+        assert_eq!(nvme.read_ahead_kb, Some(128));
+
+        let sda = settings_for(&rules, |matches| Ok(matches == &Match::Any)).unwrap();
+        assert_eq!(sda.scheduler, Some(Scheduler::MqDeadline));
+    }
+}
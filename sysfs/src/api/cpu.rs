@@ -1,4 +1,9 @@
 //! <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html?highlight=schedutil#policy-interface-in-sysfs>
+use std::collections::BTreeSet;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
 use crate::lib::{sysfs_attrs, Result};
 
 pub fn num_cpus() -> Result<usize> {
@@ -18,10 +23,113 @@ pub fn num_cpus() -> Result<usize> {
     })
 }
 
+/// A set of CPU numbers.
+///
+/// This parses from and formats to the list format the kernel uses for CPU
+/// masks, for example `0-3,8,10-11`.
+///
+/// <https://www.kernel.org/doc/html/latest/admin-guide/cputopology.html>
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CpuSet(BTreeSet<usize>);
+
+impl CpuSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, cpu: usize) -> bool {
+        self.0.contains(&cpu)
+    }
+
+    pub fn insert(&mut self, cpu: usize) -> bool {
+        self.0.insert(cpu)
+    }
+
+    pub fn remove(&mut self, cpu: usize) -> bool {
+        self.0.remove(&cpu)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the CPU numbers in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+impl FromStr for CpuSet {
+    type Err = ParseIntError;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        let mut set = Self::new();
+        for range in text.trim().split(',').filter(|range| !range.is_empty()) {
+            match range.split_once('-') {
+                Some((first, last)) => set.0.extend(first.parse::<usize>()?..=last.parse()?),
+                None => {
+                    set.0.insert(range.parse()?);
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cpus = self.iter().peekable();
+        let mut first = true;
+        while let Some(start) = cpus.next() {
+            let mut end = start;
+            while cpus.next_if_eq(&(end + 1)).is_some() {
+                end += 1;
+            }
+            if !first {
+                f.write_str(",")?;
+            }
+            first = false;
+            if start == end {
+                write!(f, "{start}")?;
+            } else {
+                write!(f, "{start}-{end}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<usize> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Extend<usize> for CpuSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for CpuSet {
+    type Item = usize;
+    type IntoIter = std::collections::btree_set::IntoIter<usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
 /// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#policy-interface-in-sysfs>
 #[sysfs_attrs(in "/sys/devices/system/cpu/cpufreq/policy{cpu}")]
 pub mod cpufreq {
-    use crate::lib::sysfs;
+    use std::path::Path;
+
+    use crate::lib::{sysfs, Result};
 
     /// List of online CPUs belonging to this policy (i.e. sharing the
     /// hardware performance scaling interface represented by the policyX
@@ -98,6 +206,26 @@ pub mod cpufreq {
         ..
     }
 
+    /// List of frequencies (in kHz) the CPUs belonging to this policy can be
+    /// set to, as published by the frequency table of the scaling driver
+    /// (e.g. `acpi-cpufreq` and several ARM drivers).
+    ///
+    /// Any of these values is legal for the scaling_setspeed attribute when
+    /// the userspace governor is attached to this policy. Boost frequencies
+    /// are listed separately by the scaling_boost_frequencies attribute.
+    ///
+    /// This attribute is not present if the scaling driver in use does not
+    /// provide a frequency table.
+    #[sysfs]
+    pub fn scaling_available_frequencies(cpu: usize) -> Vec<usize> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(|int| int.parse().unwrap())
+                .collect()
+        };
+        ..
+    }
+
     /// List of CPUFreq scaling governors present in the kernel that can be
     /// attached to this policy or (if the intel_pstate scaling driver is in
     /// use) list of scaling algorithms provided by the driver that can be
@@ -112,6 +240,21 @@ pub mod cpufreq {
         ..
     }
 
+    /// List of boost frequencies (in kHz) from the frequency table of the
+    /// scaling driver, which are only reachable while boost is enabled.
+    ///
+    /// This attribute is not present if the scaling driver in use does not
+    /// provide a frequency table with boost entries.
+    #[sysfs]
+    pub fn scaling_boost_frequencies(cpu: usize) -> Vec<usize> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(|int| int.parse().unwrap())
+                .collect()
+        };
+        ..
+    }
+
     /// Current frequency of all of the CPUs belonging to this policy
     /// (in kHz).
     ///
@@ -192,6 +335,936 @@ pub mod cpufreq {
         let write = |freq: usize| format!("{freq}");
         ..
     }
+
+    /// Snaps `freq` (in kHz) to the closest value in
+    /// [`scaling_available_frequencies`] for this policy, which is a value
+    /// that can be given to [`set_scaling_setspeed`].
+    ///
+    /// When `freq` is exactly between two steps, the lower one is chosen.
+    /// Returns `None` if the driver published an empty frequency table.
+    pub fn nearest_available_frequency(cpu: usize, freq: usize) -> Result<Option<usize>> {
+        Ok(nearest_frequency(
+            &scaling_available_frequencies(cpu)?,
+            freq,
+        ))
+    }
+
+    /// Finds the entry of `table` closest to `freq`, preferring the lower
+    /// entry on a tie. The table does not need to be sorted.
+    pub fn nearest_frequency(table: &[usize], freq: usize) -> Option<usize> {
+        table
+            .iter()
+            .copied()
+            .min_by_key(|&step| (step.abs_diff(freq), step))
+    }
+
+    /// Directory holding the tunables of `governor` for this policy.
+    ///
+    /// Depending on whether the platform has a governor per policy
+    /// (`have_governor_per_policy` in the kernel), the tunables live either
+    /// in a subdirectory of the policy, or globally under
+    /// `/sys/devices/system/cpu/cpufreq/<governor>`. In both cases the
+    /// directory only exists while the governor is attached to at least one
+    /// policy.
+    pub fn governor_dir(cpu: usize, governor: &str) -> String {
+        let per_policy = format!("/sys/devices/system/cpu/cpufreq/policy{cpu}/{governor}");
+        if Path::new(&per_policy).is_dir() {
+            per_policy
+        } else {
+            format!("/sys/devices/system/cpu/cpufreq/{governor}")
+        }
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/cpu-freq/cpufreq-stats.html>
+///
+/// These are only present if the kernel was built with
+/// `CONFIG_CPU_FREQ_STAT`.
+#[sysfs_attrs(in "/sys/devices/system/cpu/cpufreq/policy{cpu}/stats")]
+pub mod cpufreq_stats {
+    use crate::lib::parse::{self, Table};
+    use crate::lib::sysfs;
+
+    /// The amount of time spent in each of the frequencies supported by the
+    /// CPU, as pairs of frequency (in kHz) and time (in units of 10 ms).
+    #[sysfs]
+    pub fn time_in_state(cpu: usize) -> Vec<(usize, u64)> {
+        let try_read = parse::pairs;
+        ..
+    }
+
+    /// The total number of frequency transitions on this CPU.
+    #[sysfs]
+    pub fn total_trans(cpu: usize) -> u64 {
+        let try_read = parse::value;
+        ..
+    }
+
+    /// Fine grained information about all the CPU frequency transitions.
+    ///
+    /// Every row is the frequency (in kHz) a transition started from, and
+    /// every column the frequency it went to, with the number of such
+    /// transitions in the cells.
+    #[sysfs]
+    pub fn trans_table(cpu: usize) -> Table<usize, u64> {
+        let try_read = parse::table;
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#schedutil>
+///
+/// The tunables are only present while `schedutil` is the scaling governor
+/// of the policy.
+#[sysfs_attrs(in "{dir}")]
+pub mod schedutil {
+    use crate::lib::sysfs;
+
+    use super::cpufreq::governor_dir;
+
+    /// Minimum time (in microseconds) that has to pass between two
+    /// consecutive runs of governor computations.
+    ///
+    /// The purpose of this tunable is to reduce the scheduler context
+    /// overhead of the governor which might be excessive without it.
+    #[sysfs]
+    pub fn rate_limit_us(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "schedutil");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |us: usize| format!("{us}");
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#ondemand>
+///
+/// The tunables are only present while `ondemand` is the scaling governor
+/// of the policy.
+#[sysfs_attrs(in "{dir}")]
+pub mod ondemand {
+    use crate::lib::sysfs;
+
+    use super::cpufreq::governor_dir;
+
+    /// How often the governor checks the CPU usage, in microseconds.
+    ///
+    /// Typically, it is set to values of the order of 10000 (10 ms).
+    /// Its default value is equal to the value of
+    /// cpuinfo_transition_latency for each policy this governor is attached
+    /// to (but since the unit here is greater by 1000, this means that the
+    /// time represented by sampling_rate is 1000 times greater than the
+    /// transition latency by default).
+    #[sysfs]
+    pub fn sampling_rate(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "ondemand");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |us: usize| format!("{us}");
+        ..
+    }
+
+    /// If the estimated CPU load is above this value (in percent), the
+    /// governor will set the frequency to the maximum value allowed for the
+    /// policy. Otherwise, the selected frequency will be proportional to the
+    /// estimated CPU load.
+    #[sysfs]
+    pub fn up_threshold(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "ondemand");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: usize| format!("{percent}");
+        ..
+    }
+
+    /// If set to 1 (default 0), it will cause the CPU load estimation code
+    /// to treat the CPU time spent on waiting for I/O to complete as CPU
+    /// time spent by the CPU on running the workload.
+    #[sysfs]
+    pub fn io_is_busy(cpu: usize) -> bool {
+        let dir = governor_dir(cpu, "ondemand");
+        let read = |text: &str| text != "0";
+        let write = |busy: bool| format!("{}", busy as u8);
+        ..
+    }
+
+    /// Temporary multiplier, between 1 (default) and 100 inclusive, to
+    /// apply to the sampling_rate value if the CPU load goes above
+    /// up_threshold.
+    ///
+    /// This causes the next execution of the governor's worker routine
+    /// (after setting the frequency to the allowed maximum) to be delayed,
+    /// so the frequency stays at the maximum level for a longer time.
+    #[sysfs]
+    pub fn sampling_down_factor(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "ondemand");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |factor: usize| format!("{factor}");
+        ..
+    }
+
+    /// If set to 1 (default 0), it will cause the CPU load estimation code
+    /// to treat the CPU time spent on executing tasks with "nice" levels
+    /// greater than 0 as CPU idle time.
+    #[sysfs]
+    pub fn ignore_nice_load(cpu: usize) -> bool {
+        let dir = governor_dir(cpu, "ondemand");
+        let read = |text: &str| text != "0";
+        let write = |ignore: bool| format!("{}", ignore as u8);
+        ..
+    }
+
+    /// Reduction factor to apply to the original frequency target of the
+    /// governor (including the maximum value used when the up_threshold
+    /// value is exceeded by the estimated CPU load) or sensitivity threshold
+    /// for the AMD frequency sensitivity powersave bias driver, between 0
+    /// and 1000 inclusive.
+    ///
+    /// If the AMD frequency sensitivity powersave bias driver is not loaded,
+    /// the effective frequency to apply is given by
+    /// `f * (1 - powersave_bias / 1000)`.
+    #[sysfs]
+    pub fn powersave_bias(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "ondemand");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |bias: usize| format!("{bias}");
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpufreq.html#conservative>
+///
+/// The tunables are only present while `conservative` is the scaling
+/// governor of the policy.
+#[sysfs_attrs(in "{dir}")]
+pub mod conservative {
+    use crate::lib::sysfs;
+
+    use super::cpufreq::governor_dir;
+
+    /// How often the governor checks the CPU usage, in microseconds.
+    ///
+    /// See [`super::ondemand::sampling_rate`].
+    #[sysfs]
+    pub fn sampling_rate(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "conservative");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |us: usize| format!("{us}");
+        ..
+    }
+
+    /// Threshold value (in percent, 80 by default) used to determine the
+    /// frequency change direction.
+    ///
+    /// If the estimated CPU load is greater than this value, the frequency
+    /// will go up (by freq_step). If the load is less than this value (and
+    /// the sampling_down_factor mechanism is not in effect), the frequency
+    /// will go down. Otherwise, the frequency will not be changed.
+    #[sysfs]
+    pub fn up_threshold(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "conservative");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: usize| format!("{percent}");
+        ..
+    }
+
+    /// Threshold value (in percent, 20 by default) used to determine the
+    /// frequency change direction.
+    ///
+    /// If the estimated CPU load is less than this value, the frequency will
+    /// go down. Otherwise, the frequency will stay the same or go up (unless
+    /// up_threshold is exceeded).
+    #[sysfs]
+    pub fn down_threshold(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "conservative");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: usize| format!("{percent}");
+        ..
+    }
+
+    /// Frequency step in percent of the maximum frequency the governor is
+    /// allowed to set (scaling_max_freq), 5 by default.
+    ///
+    /// This is how much the frequency is allowed to change in one go.
+    /// Setting it to 0 will cause the default frequency step (5 percent) to
+    /// be used and setting it to 100 effectively causes the governor to
+    /// periodically switch the frequency between the scaling_min_freq and
+    /// scaling_max_freq policy limits.
+    #[sysfs]
+    pub fn freq_step(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "conservative");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: usize| format!("{percent}");
+        ..
+    }
+
+    /// Frequency decrease deferral factor, between 1 (default) and 10
+    /// inclusive.
+    ///
+    /// It effectively causes the frequency to go down sampling_down_factor
+    /// times slower than it ramps up.
+    #[sysfs]
+    pub fn sampling_down_factor(cpu: usize) -> usize {
+        let dir = governor_dir(cpu, "conservative");
+        let read = |text: &str| text.parse().unwrap();
+        let write = |factor: usize| format!("{factor}");
+        ..
+    }
+
+    /// If set to 1 (default 0), it will cause the CPU load estimation code
+    /// to treat the CPU time spent on executing tasks with "nice" levels
+    /// greater than 0 as CPU idle time.
+    #[sysfs]
+    pub fn ignore_nice_load(cpu: usize) -> bool {
+        let dir = governor_dir(cpu, "conservative");
+        let read = |text: &str| text != "0";
+        let write = |ignore: bool| format!("{}", ignore as u8);
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/core-api/cpu_hotplug.html>
+#[sysfs_attrs(in "/sys/devices/system/cpu")]
+pub mod hotplug {
+    use std::path::Path;
+
+    use super::CpuSet;
+    use crate::lib::{sysfs, Result, SysfsError};
+
+    /// CPUs that are online and being scheduled.
+    #[sysfs(sysfs_file = "online")]
+    pub fn online_cpus() -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// CPUs that are not online, because they have been taken offline or
+    /// because they exceed the limit of CPUs allowed by the kernel
+    /// configuration.
+    #[sysfs(sysfs_file = "offline")]
+    pub fn offline_cpus() -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// CPUs that have been allocated resources and can be brought online if
+    /// they are present.
+    #[sysfs(sysfs_file = "possible")]
+    pub fn possible_cpus() -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// CPUs that have been identified as being present in the system.
+    #[sysfs(sysfs_file = "present")]
+    pub fn present_cpus() -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Whether this CPU is online.
+    ///
+    /// Writing `false` takes the CPU offline, writing `true` brings it back.
+    /// CPUs which are not hot-pluggable (usually CPU0) do not have this
+    /// attribute, and both reading and writing it will fail with
+    /// [`SysfsError::UnsupportedAttribute`]. Use [`is_online`] to query any
+    /// CPU.
+    #[sysfs(in "./cpu{cpu}")]
+    pub fn online(cpu: usize) -> bool {
+        if !is_hotpluggable(cpu) {
+            return Err(SysfsError::UnsupportedAttribute);
+        }
+        let read = |text: &str| text != "0";
+        let write = |online: bool| format!("{}", online as u8);
+        ..
+    }
+
+    /// Whether this CPU can be taken offline and brought back online.
+    pub fn is_hotpluggable(cpu: usize) -> bool {
+        Path::new(&format!("/sys/devices/system/cpu/cpu{cpu}/online")).is_file()
+    }
+
+    /// Whether this CPU is online, including CPUs that are not
+    /// hot-pluggable.
+    pub fn is_online(cpu: usize) -> Result<bool> {
+        Ok(online_cpus()?.contains(cpu))
+    }
+
+    /// Brings every CPU in `cpus` online and takes every other present CPU
+    /// offline.
+    ///
+    /// CPUs are brought online before any are taken offline, so the system
+    /// never drops below the requested set in between. CPUs the kernel
+    /// refused to change are returned together with the error, which
+    /// includes CPUs that are not hot-pluggable or not present.
+    pub fn set_online_cpus(cpus: &CpuSet) -> Result<Vec<(usize, SysfsError)>> {
+        let online = online_cpus()?;
+        let present = present_cpus()?;
+        let mut refused = Vec::new();
+
+        let bring_online = cpus.iter().filter(|&cpu| !online.contains(cpu));
+        let take_offline = present
+            .iter()
+            .filter(|&cpu| online.contains(cpu) && !cpus.contains(cpu));
+
+        for (cpu, state) in bring_online
+            .map(|cpu| (cpu, true))
+            .chain(take_offline.map(|cpu| (cpu, false)))
+        {
+            if let Err(e) = set_online(cpu, state) {
+                refused.push((cpu, e));
+            }
+        }
+
+        Ok(refused)
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/hw-vuln/l1tf.html#smt-control>
+#[sysfs_attrs(in "/sys/devices/system/cpu/smt")]
+pub mod smt {
+    use std::fmt;
+    use std::str::FromStr;
+
+    use crate::lib::sysfs;
+
+    /// The state of SMT (simultaneous multithreading) control.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum SmtControl {
+        /// SMT is supported by the CPU and enabled. All logical CPUs can be
+        /// onlined and offlined without restrictions.
+        On,
+        /// SMT is supported by the CPU and disabled. Only the so called
+        /// primary SMT threads can be onlined and offlined without
+        /// restrictions. An attempt to online a non-primary sibling is
+        /// rejected.
+        Off,
+        /// Same as [`SmtControl::Off`], but the state cannot be changed.
+        /// Attempts to write to the control file are rejected.
+        ForceOff,
+        /// The processor does not support SMT. It's therefore not affected
+        /// by the SMT implications of L1TF. Attempts to write to the control
+        /// file are rejected.
+        NotSupported,
+        /// The kernel was built without SMT control support.
+        NotImplemented,
+    }
+
+    impl SmtControl {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Self::On => "on",
+                Self::Off => "off",
+                Self::ForceOff => "forceoff",
+                Self::NotSupported => "notsupported",
+                Self::NotImplemented => "notimplemented",
+            }
+        }
+    }
+
+    impl FromStr for SmtControl {
+        type Err = String;
+
+        fn from_str(text: &str) -> Result<Self, Self::Err> {
+            match text {
+                "on" => Ok(Self::On),
+                "off" => Ok(Self::Off),
+                "forceoff" => Ok(Self::ForceOff),
+                "notsupported" => Ok(Self::NotSupported),
+                "notimplemented" => Ok(Self::NotImplemented),
+                _ => Err(format!("unknown SMT control state: {text}")),
+            }
+        }
+    }
+
+    impl fmt::Display for SmtControl {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.as_str())
+        }
+    }
+
+    /// The SMT control state.
+    ///
+    /// Writing [`SmtControl::On`] or [`SmtControl::Off`] enables or disables
+    /// SMT for all cores, onlining or offlining the sibling threads.
+    /// Writing [`SmtControl::ForceOff`] disables SMT until the next reboot.
+    /// Writing any other state is rejected by the kernel.
+    #[sysfs]
+    pub fn control() -> SmtControl {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |control: SmtControl| control.as_str().to_owned();
+        ..
+    }
+
+    /// Whether SMT is active, that is, whether any core has more than one
+    /// online sibling thread.
+    #[sysfs]
+    pub fn active() -> bool {
+        let read = |text: &str| text != "0";
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/cputopology.html>
+///
+/// The topology of offline CPUs is not reported by the kernel, so the tree
+/// built by [`topology::topology`] only covers online CPUs.
+#[sysfs_attrs(in "/sys/devices/system/cpu/cpu{cpu}/topology")]
+pub mod topology {
+    use std::collections::BTreeMap;
+
+    use super::{hotplug, CpuSet};
+    use crate::lib::{sysfs, Result, SysfsError};
+
+    /// Physical package id of this CPU, typically corresponding to a
+    /// physical socket number, but the actual value is architecture and
+    /// platform dependent.
+    #[sysfs]
+    pub fn physical_package_id(cpu: usize) -> isize {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The CPU die ID of this CPU, typically unique within its package, but
+    /// the actual value is architecture and platform dependent.
+    #[sysfs]
+    pub fn die_id(cpu: usize) -> isize {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The cluster ID of this CPU. A cluster is a group of cores that share
+    /// some resource, such as an L2 cache. The value is -1 (or all ones
+    /// on some kernels) if clusters are not reported by the platform.
+    #[sysfs]
+    pub fn cluster_id(cpu: usize) -> isize {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The CPU core ID of this CPU, typically unique within its package,
+    /// but the actual value is architecture and platform dependent.
+    #[sysfs]
+    pub fn core_id(cpu: usize) -> isize {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The CPUs within the same core as this CPU (its SMT siblings).
+    #[sysfs]
+    pub fn core_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The CPUs within the same cluster as this CPU.
+    #[sysfs]
+    pub fn cluster_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The CPUs within the same die as this CPU.
+    #[sysfs]
+    pub fn die_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The CPUs within the same physical package as this CPU.
+    #[sysfs]
+    pub fn package_cpus_list(cpu: usize) -> CpuSet {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The CPU topology of the system, ordered by ids at every level.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct Topology {
+        pub packages: Vec<Package>,
+    }
+
+    /// A physical package, typically a socket.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Package {
+        pub id: isize,
+        pub dies: Vec<Die>,
+    }
+
+    /// A die within a package, for example a CCD on AMD processors.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Die {
+        pub id: isize,
+        pub clusters: Vec<Cluster>,
+    }
+
+    /// A group of cores sharing some resource. Platforms that do not report
+    /// clusters have a single cluster with an id of -1 per die.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Cluster {
+        pub id: isize,
+        pub cores: Vec<Core>,
+    }
+
+    /// A core, holding one or more hardware threads.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Core {
+        pub id: isize,
+        pub threads: CpuSet,
+    }
+
+    impl Topology {
+        /// All CPUs in the topology.
+        pub fn cpus(&self) -> CpuSet {
+            self.packages
+                .iter()
+                .flat_map(|package| package.cpus())
+                .collect()
+        }
+
+        pub fn package(&self, id: isize) -> Option<&Package> {
+            self.packages.iter().find(|package| package.id == id)
+        }
+
+        pub fn dies(&self) -> impl Iterator<Item = &Die> {
+            self.packages.iter().flat_map(|package| &package.dies)
+        }
+
+        pub fn clusters(&self) -> impl Iterator<Item = &Cluster> {
+            self.dies().flat_map(|die| &die.clusters)
+        }
+
+        pub fn cores(&self) -> impl Iterator<Item = &Core> {
+            self.clusters().flat_map(|cluster| &cluster.cores)
+        }
+
+        /// The lowest numbered thread of every core, which is useful to run
+        /// one thread per core.
+        pub fn first_thread_per_core(&self) -> CpuSet {
+            self.cores()
+                .filter_map(|core| core.threads.iter().next())
+                .collect()
+        }
+    }
+
+    impl Package {
+        pub fn cpus(&self) -> CpuSet {
+            self.dies.iter().flat_map(|die| die.cpus()).collect()
+        }
+
+        pub fn die(&self, id: isize) -> Option<&Die> {
+            self.dies.iter().find(|die| die.id == id)
+        }
+    }
+
+    impl Die {
+        pub fn cpus(&self) -> CpuSet {
+            self.clusters
+                .iter()
+                .flat_map(|cluster| cluster.cpus())
+                .collect()
+        }
+
+        pub fn cores(&self) -> impl Iterator<Item = &Core> {
+            self.clusters.iter().flat_map(|cluster| &cluster.cores)
+        }
+    }
+
+    impl Cluster {
+        pub fn cpus(&self) -> CpuSet {
+            self.cores
+                .iter()
+                .flat_map(|core| core.threads.iter())
+                .collect()
+        }
+    }
+
+    /// Reads an optional id, for attributes which older kernels or some
+    /// architectures do not provide.
+    fn optional_id(id: Result<isize>, fallback: isize) -> Result<isize> {
+        match id {
+            Err(SysfsError::MissingAttribute) => Ok(fallback),
+            // Kernels before 5.16 report an unknown cluster id as `u32::MAX`.
+            Ok(id) if id == u32::MAX as isize => Ok(-1),
+            id => id,
+        }
+    }
+
+    /// Builds the topology tree of all online CPUs.
+    pub fn topology() -> Result<Topology> {
+        type Cores = BTreeMap<isize, CpuSet>;
+        let mut tree = BTreeMap::<isize, BTreeMap<isize, BTreeMap<isize, Cores>>>::new();
+
+        for cpu in hotplug::online_cpus()?.iter() {
+            let package = physical_package_id(cpu)?;
+            let die = optional_id(die_id(cpu), 0)?;
+            let cluster = optional_id(cluster_id(cpu), -1)?;
+            let core = core_id(cpu)?;
+            tree.entry(package)
+                .or_default()
+                .entry(die)
+                .or_default()
+                .entry(cluster)
+                .or_default()
+                .entry(core)
+                .or_default()
+                .insert(cpu);
+        }
+
+        let packages = tree
+            .into_iter()
+            .map(|(id, dies)| Package {
+                id,
+                dies: dies
+                    .into_iter()
+                    .map(|(id, clusters)| Die {
+                        id,
+                        clusters: clusters
+                            .into_iter()
+                            .map(|(id, cores)| Cluster {
+                                id,
+                                cores: cores
+                                    .into_iter()
+                                    .map(|(id, threads)| Core { id, threads })
+                                    .collect(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Topology { packages })
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/pm/cpuidle.html#idle-states-representation-in-sysfs>
+#[sysfs_attrs(in "/sys/devices/system/cpu")]
+pub mod cpuidle {
+    use std::convert::Infallible;
+    use std::fmt;
+    use std::str::FromStr;
+
+    use super::hotplug;
+    use crate::lib::{sysfs, Result, SysfsError};
+
+    /// A cpuidle governor.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum Governor {
+        /// Predicts the idle duration from the next timer event, corrected
+        /// by recent idle durations. The default on tickless systems.
+        Menu,
+        /// The Timer Events Oriented governor, which predicts the idle
+        /// duration mostly from timer events and how well past idle periods
+        /// matched them.
+        Teo,
+        /// Steps up and down one idle state at a time. The default on
+        /// systems with a periodic tick.
+        Ladder,
+        /// Polls for a while before entering an idle state, which is meant
+        /// for virtual machines.
+        Haltpoll,
+        /// A governor unknown to this crate.
+        Other(String),
+    }
+
+    impl Governor {
+        pub fn as_str(&self) -> &str {
+            match self {
+                Self::Menu => "menu",
+                Self::Teo => "teo",
+                Self::Ladder => "ladder",
+                Self::Haltpoll => "haltpoll",
+                Self::Other(name) => name,
+            }
+        }
+    }
+
+    impl FromStr for Governor {
+        type Err = Infallible;
+
+        fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+            Ok(match text {
+                "menu" => Self::Menu,
+                "teo" => Self::Teo,
+                "ladder" => Self::Ladder,
+                "haltpoll" => Self::Haltpoll,
+                other => Self::Other(other.to_owned()),
+            })
+        }
+    }
+
+    impl fmt::Display for Governor {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.as_str())
+        }
+    }
+
+    /// The number of idle states provided for this CPU.
+    pub fn num_states(cpu: usize) -> Result<usize> {
+        std::fs::read_dir(format!("/sys/devices/system/cpu/cpu{cpu}/cpuidle"))?.try_fold(
+            0,
+            |acc, res| {
+                let inode = res?;
+                let name = inode.file_name();
+                let name = name.to_string_lossy();
+                let is_state = name.starts_with("state")
+                    && name["state".len()..].chars().all(|ch| ch.is_ascii_digit());
+                Ok(acc + is_state as usize)
+            },
+        )
+    }
+
+    /// The name of the cpuidle driver currently in use.
+    #[sysfs(in "./cpuidle")]
+    pub fn current_driver() -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// The cpuidle governors registered with the kernel, which can be
+    /// written to [`set_current_governor`].
+    #[sysfs(in "./cpuidle")]
+    pub fn available_governors() -> Vec<Governor> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(|gov| gov.parse().unwrap())
+                .collect()
+        };
+        ..
+    }
+
+    /// The cpuidle governor currently in use.
+    ///
+    /// This attribute is read-write, and writing the name of one of the
+    /// [`available_governors`] switches to it. Use [`select_governor`] to
+    /// validate the governor first.
+    ///
+    /// Kernels from before the governor could be switched at runtime only
+    /// provide [`current_governor_ro`].
+    #[sysfs(in "./cpuidle")]
+    pub fn current_governor() -> Governor {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |governor: &Governor| governor.as_str().to_owned();
+        ..
+    }
+
+    /// The cpuidle governor currently in use, as a read-only attribute.
+    #[sysfs(in "./cpuidle")]
+    pub fn current_governor_ro() -> Governor {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Switches to `governor` after checking that it is one of the
+    /// [`available_governors`].
+    pub fn select_governor(governor: &Governor) -> Result<()> {
+        if !available_governors()?.contains(governor) {
+            return Err(SysfsError::InvalidValue(format!(
+                "the cpuidle governor `{governor}` is not available"
+            )));
+        }
+        set_current_governor(governor)
+    }
+
+    /// Name of the idle state.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn name(cpu: usize, state: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Description of the idle state.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn desc(cpu: usize, state: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Exit latency of the idle state in microseconds.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn latency(cpu: usize, state: usize) -> usize {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Target residency of the idle state in microseconds.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn residency(cpu: usize, state: usize) -> usize {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Total number of times the hardware has been asked by the given CPU
+    /// to enter this idle state.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn usage(cpu: usize, state: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Total time spent in this idle state by the given CPU (as measured by
+    /// the kernel) in microseconds.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn time(cpu: usize, state: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Whether or not this idle state is disabled.
+    ///
+    /// If the idle state is disabled, the governor will never select it for
+    /// this particular CPU and the cpuidle driver will never ask the
+    /// hardware to enter it for that CPU as a result. However, disabling an
+    /// idle state for one CPU does not prevent it from being asked for by
+    /// the other CPUs, so it must be disabled for all of them in order to
+    /// never be asked for by any of them.
+    ///
+    /// If the driver itself disabled the state, writing `false` has no
+    /// effect.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn disable(cpu: usize, state: usize) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |disable: bool| format!("{}", disable as u8);
+        ..
+    }
+
+    /// Total number of times this idle state had been asked for, but the
+    /// observed idle duration was certainly too short to match its target
+    /// residency.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn above(cpu: usize, state: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Total number of times this idle state had been asked for, but
+    /// certainly a deeper idle state would have been a better match for the
+    /// observed idle duration.
+    #[sysfs(in "./cpu{cpu}/cpuidle/state{state}")]
+    pub fn below(cpu: usize, state: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Disables every idle state with an exit latency above `max_latency`
+    /// (in microseconds) on all online CPUs.
+    ///
+    /// The other states are left as they are, unless `reenable` is set, in
+    /// which case they are enabled, undoing states that were disabled by
+    /// hand or by another profile.
+    ///
+    /// This keeps wake-up latency bounded for latency sensitive workloads
+    /// such as low-latency audio, at the cost of power consumption.
+    pub fn limit_latency(max_latency: usize, reenable: bool) -> Result<()> {
+        for cpu in hotplug::online_cpus()?.iter() {
+            for state in 0..num_states(cpu)? {
+                if latency(cpu, state)? > max_latency {
+                    set_disable(cpu, state, true)?;
+                } else if reenable {
+                    set_disable(cpu, state, false)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 // Currently the functions in here are all prefixed with `amd_pstate`.
@@ -331,3 +1404,31 @@ pub mod acpi_cppc {
         ..
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_set_round_trip() {
+        let set: CpuSet = "0-3,8,10-11".parse().unwrap();
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(set.to_string(), "0-3,8,10-11");
+        assert!(CpuSet::from_str("").unwrap().is_empty());
+        assert!(CpuSet::from_str("0-x").is_err());
+    }
+
+    #[test]
+    fn nearest_frequency_prefers_lower_on_tie() {
+        let table = [2_200_000, 1_400_000, 1_800_000];
+        assert_eq!(
+            cpufreq::nearest_frequency(&table, 1_600_000),
+            Some(1_400_000)
+        );
+        assert_eq!(
+            cpufreq::nearest_frequency(&table, 3_000_000),
+            Some(2_200_000)
+        );
+        assert_eq!(cpufreq::nearest_frequency(&[], 3_000_000), None);
+    }
+}
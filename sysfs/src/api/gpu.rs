@@ -0,0 +1,606 @@
+//! GPUs are identified by the number of their DRM card, the `N` in
+//! `/sys/class/drm/cardN`.
+use crate::lib::{sysfs_attrs, sysfs_list, sysfs_list_indexed, Result, SysfsError};
+
+/// The numbers of all DRM cards.
+pub fn cards() -> Result<Vec<usize>> {
+    sysfs_list_indexed("/sys/class/drm", "card")
+}
+
+/// The name of the kernel driver bound to a card, such as `amdgpu`, `i915`
+/// or `xe`.
+pub fn driver(card: usize) -> Result<String> {
+    let link = std::fs::read_link(format!("/sys/class/drm/card{card}/device/driver")).map_err(
+        |e| match e.kind() {
+            std::io::ErrorKind::NotFound => SysfsError::MissingAttribute,
+            _ => SysfsError::from(e),
+        },
+    )?;
+    Ok(link
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default())
+}
+
+/// The numbers of the cards bound to `driver`.
+pub fn find_cards(driver: &str) -> Result<Vec<usize>> {
+    let mut found = cards()?;
+    found.retain(|&card| matches!(self::driver(card), Ok(name) if name == driver));
+    Ok(found)
+}
+
+/// The hwmon chip of a card, see [`crate::api::hwmon`].
+pub fn hwmon(card: usize) -> Result<usize> {
+    let dir = format!("/sys/class/drm/card{card}/device/hwmon");
+    sysfs_list(&dir)?
+        .iter()
+        .find_map(|name| name.strip_prefix("hwmon")?.parse().ok())
+        .ok_or(SysfsError::MissingAttribute)
+}
+
+/// Writes a minimum and maximum frequency in MHz after checking them
+/// against the range the hardware supports, `rpn` to `rp0`.
+///
+/// They are written in the order that keeps the minimum below the maximum
+/// at every step, since the drivers reject writes that would invert them.
+fn set_freq_range_checked(
+    (min, max): (u32, u32),
+    (rpn, rp0): (u32, u32),
+    current_max: u32,
+    set_min: impl FnOnce(u32) -> Result<()>,
+    set_max: impl FnOnce(u32) -> Result<()>,
+) -> Result<()> {
+    if min > max {
+        return Err(SysfsError::InvalidValue(format!(
+            "the minimum GPU frequency {min} MHz is above the maximum {max} MHz"
+        )));
+    }
+    if min < rpn || max > rp0 {
+        return Err(SysfsError::InvalidValue(format!(
+            "the GPU frequency range {min}-{max} MHz is outside of {rpn}-{rp0} MHz"
+        )));
+    }
+
+    // Raising the minimum above the current maximum would invert them, so
+    // the maximum goes first in that case.
+    if min > current_max {
+        set_max(max)?;
+        set_min(min)
+    } else {
+        set_min(min)?;
+        set_max(max)
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/gpu/amdgpu/thermal.html>
+#[sysfs_attrs(in "/sys/class/drm/card{card}/device")]
+pub mod amdgpu {
+    use std::fmt;
+    use std::str::FromStr;
+
+    use crate::api::hwmon;
+    use crate::lib::{sysfs, Result, SysfsError};
+
+    /// The cards driven by `amdgpu`.
+    pub fn cards() -> Result<Vec<usize>> {
+        super::find_cards("amdgpu")
+    }
+
+    /// How the driver selects power states.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum PerformanceLevel {
+        /// The driver dynamically selects the optimal power profile for the
+        /// current conditions.
+        Auto,
+        /// Clocks are forced to the lowest power state.
+        Low,
+        /// Clocks are forced to the highest power state.
+        High,
+        /// The user can manually adjust which power states are enabled for
+        /// each clock domain through the `pp_dpm_*` attributes, and can
+        /// select a [`PowerProfile`] through `pp_power_profile_mode`.
+        Manual,
+        /// Clocks are set to a fixed profile level for stable performance
+        /// measurements.
+        ProfileStandard,
+        /// Forces the shader clock to its lowest level.
+        ProfileMinSclk,
+        /// Forces the memory clock to its lowest level.
+        ProfileMinMclk,
+        /// Forces all clocks to their highest level.
+        ProfilePeak,
+        /// Clocks are controlled by the `pp_od_clk_voltage` settings on
+        /// APUs that support it.
+        PerfDeterminism,
+    }
+
+    impl PerformanceLevel {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Self::Auto => "auto",
+                Self::Low => "low",
+                Self::High => "high",
+                Self::Manual => "manual",
+                Self::ProfileStandard => "profile_standard",
+                Self::ProfileMinSclk => "profile_min_sclk",
+                Self::ProfileMinMclk => "profile_min_mclk",
+                Self::ProfilePeak => "profile_peak",
+                Self::PerfDeterminism => "perf_determinism",
+            }
+        }
+    }
+
+    impl FromStr for PerformanceLevel {
+        type Err = String;
+
+        fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+            match text {
+                "auto" => Ok(Self::Auto),
+                "low" => Ok(Self::Low),
+                "high" => Ok(Self::High),
+                "manual" => Ok(Self::Manual),
+                "profile_standard" => Ok(Self::ProfileStandard),
+                "profile_min_sclk" => Ok(Self::ProfileMinSclk),
+                "profile_min_mclk" => Ok(Self::ProfileMinMclk),
+                "profile_peak" => Ok(Self::ProfilePeak),
+                "perf_determinism" => Ok(Self::PerfDeterminism),
+                _ => Err(format!("unknown performance level: {text}")),
+            }
+        }
+    }
+
+    impl fmt::Display for PerformanceLevel {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.as_str())
+        }
+    }
+
+    /// A power state of a clock domain.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct DpmLevel {
+        pub index: usize,
+        pub mhz: u32,
+        /// Whether this is the level the clock is currently running at,
+        /// marked with a `*` by the kernel.
+        pub active: bool,
+    }
+
+    /// Parses a DPM table, such as the contents of `pp_dpm_sclk`:
+    ///
+    /// ```text
+    /// 0: 500Mhz
+    /// 1: 800Mhz *
+    /// 2: 1900Mhz
+    /// ```
+    ///
+    /// Entries that are not numbered, such as the deep sleep level `S:` on
+    /// some APUs, are skipped.
+    pub fn parse_dpm_levels(text: &str) -> Vec<DpmLevel> {
+        text.lines()
+            .filter_map(|line| {
+                let (index, rest) = line.split_once(':')?;
+                let index = index.trim().parse().ok()?;
+                let rest = rest.trim();
+                let active = rest.ends_with('*');
+                let mhz = rest
+                    .trim_end_matches('*')
+                    .trim()
+                    .trim_end_matches(|ch: char| ch.is_ascii_alphabetic())
+                    .parse()
+                    .ok()?;
+                Some(DpmLevel { index, mhz, active })
+            })
+            .collect()
+    }
+
+    /// An entry of the power profile table.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct PowerProfile {
+        pub index: usize,
+        /// The name of the profile, such as `BOOTUP_DEFAULT`,
+        /// `3D_FULL_SCREEN`, `POWER_SAVING`, `VIDEO`, `VR`, `COMPUTE` or
+        /// `CUSTOM`.
+        pub name: String,
+        /// Whether this is the selected profile, marked with a `*` by the
+        /// kernel.
+        pub active: bool,
+    }
+
+    /// Parses the contents of `pp_power_profile_mode`.
+    ///
+    /// The layout of the table differs between ASICs, but every profile
+    /// starts a line with its index and name, with a `*` after the name of
+    /// the selected one. The `*` is either attached to the name or, on smu7
+    /// ASICs such as Polaris, in a column of its own. Headers and the
+    /// per-clock heuristics of some ASICs are skipped.
+    pub fn parse_power_profiles(text: &str) -> Vec<PowerProfile> {
+        text.lines()
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace();
+                let index = tokens.next()?.parse().ok()?;
+                let name = tokens.next()?.trim_end_matches(':');
+                let active = name.ends_with('*') || matches!(tokens.next(), Some("*" | "*:"));
+                let name = name.trim_end_matches('*');
+                name.starts_with(|ch: char| ch.is_ascii_alphanumeric())
+                    .then(|| PowerProfile {
+                        index,
+                        name: name.to_owned(),
+                        active,
+                    })
+            })
+            .collect()
+    }
+
+    /// A section of the `pp_od_clk_voltage` table, such as `OD_SCLK` or
+    /// `OD_RANGE`, with the lines that belong to it.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct OdSection {
+        pub name: String,
+        pub lines: Vec<String>,
+    }
+
+    /// Parses the contents of `pp_od_clk_voltage` into its sections.
+    pub fn parse_od_sections(text: &str) -> Vec<OdSection> {
+        let mut sections = Vec::<OdSection>::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            match line.strip_suffix(':') {
+                Some(name) if name.starts_with("OD_") => sections.push(OdSection {
+                    name: name.to_owned(),
+                    lines: Vec::new(),
+                }),
+                _ => {
+                    if let Some(section) = sections.last_mut() {
+                        section.lines.push(line.to_owned());
+                    }
+                }
+            }
+        }
+        sections
+    }
+
+    /// Forces the driver to use a certain performance level, or lets it
+    /// select levels automatically.
+    #[sysfs]
+    pub fn power_dpm_force_performance_level(card: usize) -> PerformanceLevel {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |level: PerformanceLevel| level.as_str().to_owned();
+        ..
+    }
+
+    /// The power profiles of the card, with the selected one marked.
+    ///
+    /// Writing the index of a profile selects it, which requires the
+    /// [`PerformanceLevel::Manual`] performance level.
+    #[sysfs]
+    pub fn pp_power_profile_mode(card: usize) -> Vec<PowerProfile> {
+        let read = |text: &str| parse_power_profiles(text);
+        let write = |index: usize| format!("{index}");
+        ..
+    }
+
+    /// The power states of the graphics (shader) clock.
+    ///
+    /// Writing a list of level indices restricts the clock to them, which
+    /// requires the [`PerformanceLevel::Manual`] performance level.
+    #[sysfs]
+    pub fn pp_dpm_sclk(card: usize) -> Vec<DpmLevel> {
+        let read = |text: &str| parse_dpm_levels(text);
+        let write = |levels: &[usize]| {
+            levels
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        ..
+    }
+
+    /// The power states of the memory clock.
+    ///
+    /// Writing a list of level indices restricts the clock to them, which
+    /// requires the [`PerformanceLevel::Manual`] performance level.
+    #[sysfs]
+    pub fn pp_dpm_mclk(card: usize) -> Vec<DpmLevel> {
+        let read = |text: &str| parse_dpm_levels(text);
+        let write = |levels: &[usize]| {
+            levels
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        ..
+    }
+
+    /// The overdrive clock and voltage table.
+    ///
+    /// Writing takes a command such as `s 1 2000` (set shader clock level 1
+    /// to 2000 MHz), `r` (reset) or `c` (commit). Which commands are
+    /// accepted depends on the ASIC.
+    #[sysfs]
+    pub fn pp_od_clk_voltage(card: usize) -> Vec<OdSection> {
+        let read = |text: &str| parse_od_sections(text);
+        let write = |command: &str| command.to_owned();
+        ..
+    }
+
+    /// How busy the GPU is, in percent.
+    #[sysfs]
+    pub fn gpu_busy_percent(card: usize) -> u8 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Selects the power profile called `name` after switching to the
+    /// [`PerformanceLevel::Manual`] performance level.
+    pub fn select_power_profile(card: usize, name: &str) -> Result<()> {
+        let profile = pp_power_profile_mode(card)?
+            .into_iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| {
+                SysfsError::InvalidValue(format!("card{card} has no power profile `{name}`"))
+            })?;
+        set_power_dpm_force_performance_level(card, PerformanceLevel::Manual)?;
+        set_pp_power_profile_mode(card, profile.index)
+    }
+
+    /// The power cap of the card in watts, from its hwmon `power1_cap`.
+    pub fn power_cap(card: usize) -> Result<f64> {
+        hwmon::chip::power_cap(super::hwmon(card)?, 1)
+    }
+
+    /// Sets the power cap of the card in watts, through its hwmon
+    /// `power1_cap`.
+    pub fn set_power_cap(card: usize, watts: f64) -> Result<()> {
+        hwmon::chip::set_power_cap(super::hwmon(card)?, 1, watts)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn dpm_levels() {
+            let levels = parse_dpm_levels("0: 500Mhz \n1: 800Mhz *\nS: 19Mhz\n2: 1900Mhz");
+            assert_eq!(
+                levels,
+                [
+                    DpmLevel {
+                        index: 0,
+                        mhz: 500,
+                        active: false
+                    },
+                    DpmLevel {
+                        index: 1,
+                        mhz: 800,
+                        active: true
+                    },
+                    DpmLevel {
+                        index: 2,
+                        mhz: 1900,
+                        active: false
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn power_profiles() {
+            let vega = "NUM        MODE_NAME BUSY_SET_POINT FPS USE_RLC_BUSY MIN_ACTIVE_LEVEL\n  \
+                        0 BOOTUP_DEFAULT :             70       60          0              0\n  \
+                        1 3D_FULL_SCREEN*:             70       60          1              3";
+            let navi = "PROFILE_INDEX(NAME) CLOCK_TYPE(NAME) FPS MinFreqType\n \
+                        0 BOOTUP_DEFAULT :\n                    \
+                        0(       GFXCLK)       0       5\n \
+                        1 3D_FULL_SCREEN*:\n                    \
+                        0(       GFXCLK)       0       5";
+            let polaris = "NUM        MODE_NAME     SCLK_UP_HYST   SCLK_DOWN_HYST\n  \
+                           0 BOOTUP_DEFAULT  :        0              100\n  \
+                           1 3D_FULL_SCREEN *:        0              100";
+            let simple = " 0 BOOTUP_DEFAULT\n 1 3D_FULL_SCREEN*\n 2 POWER_SAVING";
+            for text in [vega, navi, polaris, simple] {
+                let profiles = parse_power_profiles(text);
+                assert_eq!(profiles[0].name, "BOOTUP_DEFAULT");
+                assert!(!profiles[0].active);
+                assert_eq!(profiles[1].name, "3D_FULL_SCREEN");
+                assert!(profiles[1].active);
+            }
+        }
+
+        #[test]
+        fn od_sections() {
+            let sections = parse_od_sections(
+                "OD_SCLK:\n0: 500Mhz\n1: 2529Mhz\nOD_RANGE:\nSCLK:     500Mhz       3150Mhz",
+            );
+            assert_eq!(sections.len(), 2);
+            assert_eq!(sections[0].name, "OD_SCLK");
+            assert_eq!(sections[0].lines, ["0: 500Mhz", "1: 2529Mhz"]);
+            assert_eq!(sections[1].lines, ["SCLK:     500Mhz       3150Mhz"]);
+        }
+    }
+}
+
+/// Frequency controls of the `i915` driver, which live in the card
+/// directory. All frequencies are in MHz.
+///
+/// Cards driven by `xe` expose their frequencies per tile and GT instead,
+/// see [`xe`].
+#[sysfs_attrs(in "/sys/class/drm/card{card}")]
+pub mod intel {
+    use crate::lib::{sysfs, Result};
+
+    /// The cards driven by `i915`.
+    pub fn cards() -> Result<Vec<usize>> {
+        super::find_cards("i915")
+    }
+
+    /// The minimum frequency the GPU is allowed to run at.
+    #[sysfs]
+    pub fn gt_min_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |mhz: u32| format!("{mhz}");
+        ..
+    }
+
+    /// The maximum frequency the GPU is allowed to run at.
+    #[sysfs]
+    pub fn gt_max_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |mhz: u32| format!("{mhz}");
+        ..
+    }
+
+    /// The frequency the GPU is raised to when a waiting client needs it
+    /// to catch up.
+    #[sysfs]
+    pub fn gt_boost_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |mhz: u32| format!("{mhz}");
+        ..
+    }
+
+    /// The frequency last requested by the driver.
+    #[sysfs]
+    pub fn gt_cur_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The frequency the GPU is actually running at.
+    #[sysfs]
+    pub fn gt_act_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The highest frequency the hardware supports (render performance
+    /// state 0).
+    #[sysfs(sysfs_file = "gt_RP0_freq_mhz")]
+    pub fn gt_rp0_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The most efficient frequency of the hardware (render performance
+    /// state 1).
+    #[sysfs(sysfs_file = "gt_RP1_freq_mhz")]
+    pub fn gt_rp1_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The lowest frequency the hardware supports (render performance
+    /// state n).
+    #[sysfs(sysfs_file = "gt_RPn_freq_mhz")]
+    pub fn gt_rpn_freq_mhz(card: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Sets the minimum and maximum frequency of the GPU in MHz, like
+    /// [`crate::api::cpu::cpufreq::set_scaling_max_freq`] does for CPUs.
+    ///
+    /// Both must lie between [`gt_rpn_freq_mhz`] and [`gt_rp0_freq_mhz`],
+    /// and `min` must not be above `max`. They are written in the order
+    /// that keeps the minimum below the maximum at every step, since the
+    /// driver rejects writes that would invert them.
+    pub fn set_freq_range(card: usize, min: u32, max: u32) -> Result<()> {
+        super::set_freq_range_checked(
+            (min, max),
+            (gt_rpn_freq_mhz(card)?, gt_rp0_freq_mhz(card)?),
+            gt_max_freq_mhz(card)?,
+            |min| set_gt_min_freq_mhz(card, min),
+            |max| set_gt_max_freq_mhz(card, max),
+        )
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/gpu/xe/xe_gt_freq.html>
+///
+/// Frequency controls of the `xe` driver. Every tile of a card has one or
+/// more GTs, such as the render and media GT, each with its own frequency
+/// limits. GT numbers are unique across the tiles of a card. All
+/// frequencies are in MHz.
+#[sysfs_attrs(in "/sys/class/drm/card{card}/device/tile{tile}/gt{gt}/freq0")]
+pub mod xe {
+    use crate::lib::{sysfs, sysfs_list_indexed, Result};
+
+    /// The cards driven by `xe`.
+    pub fn cards() -> Result<Vec<usize>> {
+        super::find_cards("xe")
+    }
+
+    /// The tiles and GTs of a card, as pairs of `(tile, gt)`.
+    pub fn gts(card: usize) -> Result<Vec<(usize, usize)>> {
+        let dir = format!("/sys/class/drm/card{card}/device");
+        let mut gts = Vec::new();
+        for tile in sysfs_list_indexed(&dir, "tile")? {
+            for gt in sysfs_list_indexed(&format!("{dir}/tile{tile}"), "gt")? {
+                gts.push((tile, gt));
+            }
+        }
+        Ok(gts)
+    }
+
+    /// The minimum frequency the GT is allowed to run at.
+    #[sysfs]
+    pub fn min_freq(card: usize, tile: usize, gt: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |mhz: u32| format!("{mhz}");
+        ..
+    }
+
+    /// The maximum frequency the GT is allowed to run at.
+    #[sysfs]
+    pub fn max_freq(card: usize, tile: usize, gt: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |mhz: u32| format!("{mhz}");
+        ..
+    }
+
+    /// The frequency last requested by the driver.
+    #[sysfs]
+    pub fn cur_freq(card: usize, tile: usize, gt: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The frequency the GT is actually running at, `0` while it is idle.
+    #[sysfs]
+    pub fn act_freq(card: usize, tile: usize, gt: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The highest frequency the hardware supports (render performance
+    /// state 0).
+    #[sysfs]
+    pub fn rp0_freq(card: usize, tile: usize, gt: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The lowest frequency the hardware supports (render performance
+    /// state n).
+    #[sysfs]
+    pub fn rpn_freq(card: usize, tile: usize, gt: usize) -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Sets the minimum and maximum frequency of a GT in MHz, like
+    /// [`super::intel::set_freq_range`] does for `i915`.
+    ///
+    /// Both must lie between [`rpn_freq`] and [`rp0_freq`], and `min` must
+    /// not be above `max`. They are written in the same order as for
+    /// `i915`, keeping the minimum below the maximum at every step.
+    pub fn set_freq_range(card: usize, tile: usize, gt: usize, min: u32, max: u32) -> Result<()> {
+        super::set_freq_range_checked(
+            (min, max),
+            (rpn_freq(card, tile, gt)?, rp0_freq(card, tile, gt)?),
+            max_freq(card, tile, gt)?,
+            |min| set_min_freq(card, tile, gt, min),
+            |max| set_max_freq(card, tile, gt, max),
+        )
+    }
+}
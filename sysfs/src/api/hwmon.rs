@@ -0,0 +1,437 @@
+//! <https://www.kernel.org/doc/html/latest/hwmon/sysfs-interface.html>
+//!
+//! Hardware monitoring chips are identified by the number in their
+//! directory name, `hwmonN`, and their channels by the number in the
+//! attribute names, such as the `1` in `temp1_input`. Channel numbers start
+//! at 1 for most sensor types, but at 0 for voltages.
+//!
+//! The kernel reports values in millidegrees, millivolts, milliamperes and
+//! microwatts. The functions in here convert them to degrees Celsius,
+//! volts, amperes and watts. Fan speeds are in RPM.
+use std::fmt;
+
+use crate::lib::{sysfs_attrs, sysfs_list, sysfs_list_indexed, sysfs_read, Result, SysfsError};
+
+/// The kind of quantity a sensor measures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SensorKind {
+    /// In degrees Celsius.
+    Temperature,
+    /// In RPM.
+    Fan,
+    /// In volts.
+    Voltage,
+    /// In amperes.
+    Current,
+    /// In watts.
+    Power,
+}
+
+impl SensorKind {
+    /// The prefix of the attributes of this kind, such as `temp`.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Temperature => "temp",
+            Self::Fan => "fan",
+            Self::Voltage => "in",
+            Self::Current => "curr",
+            Self::Power => "power",
+        }
+    }
+
+    /// The SI unit (or RPM for fans) of readings of this kind.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Temperature => "°C",
+            Self::Fan => "RPM",
+            Self::Voltage => "V",
+            Self::Current => "A",
+            Self::Power => "W",
+        }
+    }
+
+    /// The factor from the unit used by the kernel to the unit of
+    /// [`SensorKind::unit`].
+    fn scale(&self) -> f64 {
+        match self {
+            Self::Temperature | Self::Voltage | Self::Current => 1e-3,
+            Self::Fan => 1.0,
+            Self::Power => 1e-6,
+        }
+    }
+
+    /// The attribute holding the measured value. Power meters usually
+    /// report an average rather than an instantaneous value.
+    fn value_suffixes(&self) -> &'static [&'static str] {
+        match self {
+            Self::Power => &["_average", "_input"],
+            _ => &["_input"],
+        }
+    }
+
+    const ALL: [Self; 5] = [
+        Self::Temperature,
+        Self::Fan,
+        Self::Voltage,
+        Self::Current,
+        Self::Power,
+    ];
+}
+
+/// A sensor reading of a hwmon chip.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reading {
+    pub kind: SensorKind,
+    pub channel: usize,
+    /// The label of the channel, such as `Tctl` or `Composite`, falling back
+    /// to the attribute prefix and channel, such as `temp1`.
+    pub label: String,
+    /// The value in the unit of [`SensorKind::unit`].
+    pub value: f64,
+}
+
+impl fmt::Display for Reading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:.2} {}", self.label, self.value, self.kind.unit())
+    }
+}
+
+/// The numbers of all hwmon chips.
+pub fn chips() -> Result<Vec<usize>> {
+    sysfs_list_indexed("/sys/class/hwmon", "hwmon")
+}
+
+/// The numbers of the hwmon chips with the given name, such as `k10temp`,
+/// `coretemp`, `amdgpu` or `nvme`.
+pub fn find_chips(name: &str) -> Result<Vec<usize>> {
+    let mut found = Vec::new();
+    for chip in chips()? {
+        if chip::name(chip)? == name {
+            found.push(chip);
+        }
+    }
+    Ok(found)
+}
+
+/// The channels of a chip that measure `kind`, sorted.
+pub fn channels(chip: usize, kind: SensorKind) -> Result<Vec<usize>> {
+    let mut channels: Vec<usize> = sysfs_list(&format!("/sys/class/hwmon/hwmon{chip}"))?
+        .iter()
+        .filter_map(|file| {
+            let rest = file.strip_prefix(kind.prefix())?;
+            kind.value_suffixes()
+                .iter()
+                .find_map(|suffix| rest.strip_suffix(suffix)?.parse().ok())
+        })
+        .collect();
+    channels.sort_unstable();
+    channels.dedup();
+    Ok(channels)
+}
+
+/// Reads a single channel of a chip in the unit of [`SensorKind::unit`].
+pub fn read(chip: usize, kind: SensorKind, channel: usize) -> Result<f64> {
+    let prefix = kind.prefix();
+    let mut result = Err(SysfsError::MissingAttribute);
+    for suffix in kind.value_suffixes() {
+        let path = format!("/sys/class/hwmon/hwmon{chip}/{prefix}{channel}{suffix}");
+        // SAFETY: The path is a hwmon attribute in *sysfs*.
+        result = unsafe { sysfs_read(&path, |text| text.parse::<f64>().unwrap()) };
+        if !matches!(result, Err(SysfsError::MissingAttribute)) {
+            break;
+        }
+    }
+    result.map(|value| value * kind.scale())
+}
+
+/// Reads every sensor of a chip, paired with its label.
+///
+/// Channels that cannot be read are skipped, since some fail on their own,
+/// such as unconnected inputs or those of a runtime-suspended GPU. Use
+/// [`read`] to get the error of a single channel.
+pub fn readings(chip: usize) -> Result<Vec<Reading>> {
+    let mut readings = Vec::new();
+    for kind in SensorKind::ALL {
+        for channel in channels(chip, kind)? {
+            let Ok(value) = read(chip, kind, channel) else {
+                continue;
+            };
+            readings.push(Reading {
+                kind,
+                channel,
+                label: chip::label(chip, kind, channel)
+                    .unwrap_or_else(|_| format!("{}{channel}", kind.prefix())),
+                value,
+            });
+        }
+    }
+    Ok(readings)
+}
+
+/// The fan control method of a PWM output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PwmEnable {
+    /// No fan speed control, which means the fan runs at full speed.
+    FullSpeed,
+    /// Manual control through the `pwmN` attribute.
+    Manual,
+    /// Automatic control by the chip. The meaning of the values 2 and up
+    /// differs between chips, for example `nct6775` uses 5 for Smart Fan IV.
+    Automatic(u8),
+}
+
+impl PwmEnable {
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::FullSpeed => 0,
+            Self::Manual => 1,
+            Self::Automatic(mode) => *mode,
+        }
+    }
+}
+
+impl From<u8> for PwmEnable {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::FullSpeed,
+            1 => Self::Manual,
+            mode => Self::Automatic(mode),
+        }
+    }
+}
+
+/// A point of a [`FanCurve`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurvePoint {
+    /// Temperature in degrees Celsius.
+    pub temp: f64,
+    /// PWM duty cycle, from 0 (stopped) to 255 (full speed).
+    pub pwm: u8,
+}
+
+/// Maps temperatures to PWM duty cycles by linear interpolation between
+/// points, with hysteresis when the temperature falls.
+///
+/// Below the first point and above the last point, the duty cycle of that
+/// point is used. The duty cycle only decreases once the temperature has
+/// fallen `hysteresis` degrees below where the curve would produce it,
+/// which stops the fan from oscillating around a point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FanCurve {
+    points: Vec<CurvePoint>,
+    hysteresis: f64,
+    current: Option<u8>,
+}
+
+impl FanCurve {
+    /// Creates a curve from its points, which are sorted by temperature.
+    pub fn new(points: impl IntoIterator<Item = CurvePoint>, hysteresis: f64) -> Self {
+        let mut points: Vec<_> = points.into_iter().collect();
+        points.sort_by(|a, b| a.temp.total_cmp(&b.temp));
+        Self {
+            points,
+            hysteresis,
+            current: None,
+        }
+    }
+
+    /// The duty cycle of the curve at `temp`, without hysteresis.
+    pub fn pwm_at(&self, temp: f64) -> u8 {
+        let Some(first) = self.points.first() else {
+            return u8::MAX;
+        };
+        if temp <= first.temp {
+            return first.pwm;
+        }
+        for pair in self.points.windows(2) {
+            let (low, high) = (pair[0], pair[1]);
+            if temp <= high.temp {
+                let ratio = (temp - low.temp) / (high.temp - low.temp);
+                let pwm = low.pwm as f64 + ratio * (high.pwm as f64 - low.pwm as f64);
+                return pwm.round() as u8;
+            }
+        }
+        self.points[self.points.len() - 1].pwm
+    }
+
+    /// Feeds the current temperature into the curve, and returns the duty
+    /// cycle to apply.
+    pub fn update(&mut self, temp: f64) -> u8 {
+        let target = self.pwm_at(temp);
+        let pwm = match self.current {
+            Some(current) if target < current => self.pwm_at(temp + self.hysteresis).min(current),
+            _ => target,
+        };
+        self.current = Some(pwm);
+        pwm
+    }
+}
+
+/// Drives a PWM output of a chip from a temperature input with a
+/// [`FanCurve`].
+#[derive(Clone, Debug)]
+pub struct FanController {
+    /// The chip and channel of the PWM output.
+    pub pwm: (usize, usize),
+    /// The chip and channel of the temperature input.
+    pub temp: (usize, usize),
+    pub curve: FanCurve,
+    previous_enable: Option<PwmEnable>,
+}
+
+impl FanController {
+    pub fn new(pwm: (usize, usize), temp: (usize, usize), curve: FanCurve) -> Self {
+        Self {
+            pwm,
+            temp,
+            curve,
+            previous_enable: None,
+        }
+    }
+
+    /// Switches the PWM output to manual control, remembering the previous
+    /// control method for [`FanController::release`].
+    pub fn take_control(&mut self) -> Result<()> {
+        let (chip, channel) = self.pwm;
+        let previous = chip::pwm_enable(chip, channel)?;
+        chip::set_pwm_enable(chip, channel, PwmEnable::Manual)?;
+        self.previous_enable.get_or_insert(previous);
+        Ok(())
+    }
+
+    /// Reads the temperature, and writes the duty cycle from the curve to
+    /// the PWM output. Returns the duty cycle.
+    pub fn step(&mut self) -> Result<u8> {
+        let (temp_chip, temp_channel) = self.temp;
+        let (chip, channel) = self.pwm;
+        let pwm = self
+            .curve
+            .update(read(temp_chip, SensorKind::Temperature, temp_channel)?);
+        chip::set_pwm(chip, channel, pwm)?;
+        Ok(pwm)
+    }
+
+    /// Restores the control method the PWM output had before
+    /// [`FanController::take_control`].
+    pub fn release(&mut self) -> Result<()> {
+        let (chip, channel) = self.pwm;
+        match self.previous_enable.take() {
+            Some(previous) => chip::set_pwm_enable(chip, channel, previous),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Attributes of a hwmon chip.
+///
+/// Channel attributes are bound here for the most common limits; use
+/// [`read`] and [`readings`] for the measured values of any sensor kind.
+#[sysfs_attrs(in "/sys/class/hwmon/hwmon{chip}")]
+pub mod chip {
+    use super::{PwmEnable, SensorKind};
+    use crate::lib::sysfs;
+
+    /// The chip name, such as `k10temp` or `nvme`.
+    #[sysfs]
+    pub fn name(chip: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Suggested label for a channel, such as `Tctl` or `Composite`. Not
+    /// every chip provides labels.
+    #[sysfs(sysfs_file = "{prefix}{channel}_label")]
+    pub fn label(chip: usize, kind: SensorKind, channel: usize) -> String {
+        let prefix = kind.prefix();
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Temperature critical value, typically greater than the
+    /// corresponding max value, in degrees Celsius.
+    #[sysfs(sysfs_file = "temp{channel}_crit")]
+    pub fn temp_crit(chip: usize, channel: usize) -> f64 {
+        let read = |text: &str| text.parse::<f64>().unwrap() / 1000.0;
+        ..
+    }
+
+    /// Temperature max value, in degrees Celsius.
+    #[sysfs(sysfs_file = "temp{channel}_max")]
+    pub fn temp_max(chip: usize, channel: usize) -> f64 {
+        let read = |text: &str| text.parse::<f64>().unwrap() / 1000.0;
+        ..
+    }
+
+    /// Fan minimum value, in RPM.
+    #[sysfs(sysfs_file = "fan{channel}_min")]
+    pub fn fan_min(chip: usize, channel: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Average power cap, in watts. Writable on some chips such as
+    /// `amdgpu`.
+    #[sysfs(sysfs_file = "power{channel}_cap")]
+    pub fn power_cap(chip: usize, channel: usize) -> f64 {
+        let read = |text: &str| text.parse::<f64>().unwrap() / 1e6;
+        let write = |watts: f64| format!("{}", (watts * 1e6).round() as u64);
+        ..
+    }
+
+    /// Pulse width modulation fan control, from 0 (stopped) to 255 (full
+    /// speed). Only effective in [`PwmEnable::Manual`] mode.
+    #[sysfs(sysfs_file = "pwm{channel}")]
+    pub fn pwm(chip: usize, channel: usize) -> u8 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |pwm: u8| format!("{pwm}");
+        ..
+    }
+
+    /// Fan speed control method.
+    #[sysfs(sysfs_file = "pwm{channel}_enable")]
+    pub fn pwm_enable(chip: usize, channel: usize) -> PwmEnable {
+        let read = |text: &str| text.parse::<u8>().unwrap().into();
+        let write = |enable: PwmEnable| format!("{}", enable.as_u8());
+        ..
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> FanCurve {
+        FanCurve::new(
+            [
+                CurvePoint {
+                    temp: 70.0,
+                    pwm: 255,
+                },
+                CurvePoint {
+                    temp: 40.0,
+                    pwm: 55,
+                },
+            ],
+            5.0,
+        )
+    }
+
+    #[test]
+    fn fan_curve_interpolates() {
+        let curve = curve();
+        assert_eq!(curve.pwm_at(20.0), 55);
+        assert_eq!(curve.pwm_at(55.0), 155);
+        assert_eq!(curve.pwm_at(90.0), 255);
+    }
+
+    #[test]
+    fn fan_curve_hysteresis() {
+        let mut curve = curve();
+        assert_eq!(curve.update(55.0), 155);
+        // Within the hysteresis, the duty cycle is held.
+        assert_eq!(curve.update(51.0), 155);
+        // Further down, it follows the curve shifted by the hysteresis.
+        assert_eq!(curve.update(45.0), 122);
+        assert_eq!(curve.update(60.0), 188);
+    }
+}
@@ -0,0 +1,316 @@
+//! Memory management knobs under `/sys/kernel/mm`.
+//!
+//! <https://www.kernel.org/doc/html/latest/admin-guide/mm/transhuge.html>
+//! <https://www.kernel.org/doc/html/latest/admin-guide/mm/ksm.html>
+//! <https://www.kernel.org/doc/html/latest/admin-guide/mm/multigen_lru.html>
+use std::fmt;
+use std::str::FromStr;
+
+use crate::lib::sysfs_attrs;
+
+/// When transparent hugepages are used for anonymous memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ThpEnabled {
+    /// For all memory regions large enough.
+    Always,
+    /// Only for regions marked with `madvise(MADV_HUGEPAGE)`.
+    Madvise,
+    Never,
+}
+
+impl ThpEnabled {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Madvise => "madvise",
+            Self::Never => "never",
+        }
+    }
+}
+
+impl FromStr for ThpEnabled {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "always" => Ok(Self::Always),
+            "madvise" => Ok(Self::Madvise),
+            "never" => Ok(Self::Never),
+            other => Err(format!("unknown transparent hugepage mode: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for ThpEnabled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How hard the kernel tries to reclaim and compact memory when a
+/// transparent hugepage cannot be allocated right away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ThpDefrag {
+    /// Stall the allocation to reclaim and compact memory.
+    Always,
+    /// Wake `kswapd` and `kcompactd` to reclaim and compact in the
+    /// background, and fall back to regular pages.
+    Defer,
+    /// Stall for regions marked with `madvise(MADV_HUGEPAGE)`, and defer
+    /// for all others.
+    DeferMadvise,
+    /// Stall only for regions marked with `madvise(MADV_HUGEPAGE)`.
+    Madvise,
+    Never,
+}
+
+impl ThpDefrag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Defer => "defer",
+            Self::DeferMadvise => "defer+madvise",
+            Self::Madvise => "madvise",
+            Self::Never => "never",
+        }
+    }
+}
+
+impl FromStr for ThpDefrag {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "always" => Ok(Self::Always),
+            "defer" => Ok(Self::Defer),
+            "defer+madvise" => Ok(Self::DeferMadvise),
+            "madvise" => Ok(Self::Madvise),
+            "never" => Ok(Self::Never),
+            other => Err(format!("unknown transparent hugepage defrag mode: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for ThpDefrag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The state of the kernel samepage merging daemon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KsmRun {
+    /// Stop merging, but keep the pages merged so far.
+    Stop,
+    /// Merge pages.
+    Run,
+    /// Stop merging, and unmerge all pages merged so far.
+    Unmerge,
+}
+
+impl KsmRun {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stop => "0",
+            Self::Run => "1",
+            Self::Unmerge => "2",
+        }
+    }
+}
+
+impl FromStr for KsmRun {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "0" => Ok(Self::Stop),
+            "1" => Ok(Self::Run),
+            "2" => Ok(Self::Unmerge),
+            other => Err(format!("unknown KSM run state: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for KsmRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The enabled features of the multi-gen LRU, as a bit mask.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LruGenFeatures(pub u32);
+
+impl LruGenFeatures {
+    /// The multi-gen LRU itself.
+    pub const BASE: Self = Self(0x0001);
+    /// Clearing the accessed bit in leaf page table entries in large
+    /// batches.
+    pub const MM_WALK: Self = Self(0x0002);
+    /// Clearing the accessed bit in non-leaf page table entries.
+    pub const NONLEAF_YOUNG: Self = Self(0x0004);
+    pub const ALL: Self = Self(0x0007);
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl fmt::Display for LruGenFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#06x}", self.0)
+    }
+}
+
+#[sysfs_attrs(in "/sys/kernel/mm/transparent_hugepage")]
+pub mod transparent_hugepage {
+    use super::{ThpDefrag, ThpEnabled};
+    use crate::lib::{sysfs, Choice};
+
+    /// When anonymous memory is backed by transparent hugepages. Writing it
+    /// affects new allocations, while `khugepaged` collapses existing
+    /// memory over time.
+    #[sysfs]
+    pub fn enabled() -> Choice<ThpEnabled> {
+        ..
+    }
+
+    /// Whether a hugepage allocation that cannot be satisfied right away
+    /// stalls to reclaim and compact memory, wakes the background daemons,
+    /// or falls back to regular pages.
+    #[sysfs]
+    pub fn defrag() -> Choice<ThpDefrag> {
+        ..
+    }
+
+    /// Whether `khugepaged` may reclaim and compact memory to collapse
+    /// pages into hugepages.
+    #[sysfs(in "./khugepaged", sysfs_file = "defrag")]
+    pub fn khugepaged_defrag() -> bool {
+        let read = |text: &str| text != "0";
+        let write = |defrag: bool| format!("{}", defrag as u8);
+        ..
+    }
+
+    /// The number of pages `khugepaged` scans on each pass.
+    #[sysfs(in "./khugepaged")]
+    pub fn pages_to_scan() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |pages: u32| format!("{pages}");
+        ..
+    }
+
+    /// How long `khugepaged` waits between passes, in milliseconds.
+    #[sysfs(in "./khugepaged")]
+    pub fn scan_sleep_millisecs() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |millis: u32| format!("{millis}");
+        ..
+    }
+
+    /// How long `khugepaged` waits after a failed hugepage allocation, in
+    /// milliseconds.
+    #[sysfs(in "./khugepaged")]
+    pub fn alloc_sleep_millisecs() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |millis: u32| format!("{millis}");
+        ..
+    }
+
+    /// The number of unmapped pages a range may have and still be
+    /// collapsed into a hugepage, which costs that much extra memory.
+    #[sysfs(in "./khugepaged")]
+    pub fn max_ptes_none() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |ptes: u32| format!("{ptes}");
+        ..
+    }
+
+    /// The number of hugepages `khugepaged` has collapsed.
+    #[sysfs(in "./khugepaged")]
+    pub fn pages_collapsed() -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The number of complete passes `khugepaged` has made.
+    #[sysfs(in "./khugepaged")]
+    pub fn full_scans() -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+}
+
+/// Kernel samepage merging. Only memory regions marked with
+/// `madvise(MADV_MERGEABLE)` are merged.
+#[sysfs_attrs(in "/sys/kernel/mm/ksm")]
+pub mod ksm {
+    use super::KsmRun;
+    use crate::lib::sysfs;
+
+    /// Whether the KSM daemon is running. Writing [`KsmRun::Unmerge`]
+    /// stops it and splits every merged page again.
+    #[sysfs]
+    pub fn run() -> KsmRun {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |run: KsmRun| run.as_str().to_owned();
+        ..
+    }
+
+    /// The number of pages to scan before the daemon sleeps.
+    #[sysfs]
+    pub fn pages_to_scan() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |pages: u32| format!("{pages}");
+        ..
+    }
+
+    /// How long the daemon sleeps between scans, in milliseconds.
+    #[sysfs]
+    pub fn sleep_millisecs() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |millis: u32| format!("{millis}");
+        ..
+    }
+
+    /// The number of shared pages in use.
+    #[sysfs]
+    pub fn pages_shared() -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The number of pages merged into the shared pages, which is roughly
+    /// how many pages are saved.
+    #[sysfs]
+    pub fn pages_sharing() -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+}
+
+#[sysfs_attrs(in "/sys/kernel/mm/lru_gen")]
+pub mod lru_gen {
+    use super::LruGenFeatures;
+    use crate::lib::{parse, sysfs};
+
+    /// The enabled features of the multi-gen LRU. Writing `0` disables it
+    /// and falls back to the classic LRU, and [`LruGenFeatures::ALL`]
+    /// enables every feature.
+    #[sysfs]
+    pub fn enabled() -> LruGenFeatures {
+        let try_read = |text: &str| parse::hex(text).map(LruGenFeatures);
+        let write = |features: LruGenFeatures| features.to_string();
+        ..
+    }
+
+    /// Protects the working set of the last `min_ttl_ms` milliseconds from
+    /// eviction, to prevent thrashing when memory runs low. `0` disables
+    /// the protection.
+    #[sysfs]
+    pub fn min_ttl_ms() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |millis: u32| format!("{millis}");
+        ..
+    }
+}
@@ -0,0 +1,142 @@
+//! <https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-module>
+//!
+//! Parameters of kernel modules, such as `power_save` of `snd_hda_intel`,
+//! under `/sys/module/<module>/parameters`. Built-in modules with
+//! parameters are listed there as well.
+//!
+//! Only parameters the module declared as writable can be changed at
+//! runtime; the others can only be set when the module is loaded. Which
+//! ones are writable is known from the file mode, so [`write()`] checks it
+//! before writing.
+use std::fmt;
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::lib::{parse, sysfs_list, sysfs_read_text, sysfs_write, Result, SysfsError};
+
+/// The path of the file for a parameter of a module.
+///
+/// Module and parameter names that could lead outside of the module
+/// directory, because they are empty, `.` or `..`, or contain `/`, are
+/// rejected with [`SysfsError::InvalidValue`].
+pub fn path(module: &str, param: &str) -> Result<String> {
+    Ok(format!(
+        "{}/{}",
+        parameters_dir(module)?,
+        checked_name(param)?
+    ))
+}
+
+fn parameters_dir(module: &str) -> Result<String> {
+    Ok(format!("/sys/module/{}/parameters", checked_name(module)?))
+}
+
+fn checked_name(name: &str) -> Result<&str> {
+    if matches!(name, "" | "." | "..") || name.contains('/') {
+        return Err(SysfsError::InvalidValue(format!(
+            "`{name}` is not a valid module or parameter name"
+        )));
+    }
+    Ok(name)
+}
+
+/// The names of all modules in `/sys/module`, both loaded and built-in.
+pub fn modules() -> Result<Vec<String>> {
+    sysfs_list("/sys/module")
+}
+
+/// Whether the module is built into the kernel, as opposed to loaded.
+pub fn is_builtin(module: &str) -> Result<bool> {
+    let initstate = format!("/sys/module/{}/initstate", checked_name(module)?);
+    Ok(!Path::new(&initstate).exists())
+}
+
+/// The names of the parameters of a module. Modules without parameters
+/// have no `parameters` directory, so [`SysfsError::MissingAttribute`] is
+/// returned for them.
+pub fn parameters(module: &str) -> Result<Vec<String>> {
+    sysfs_list(&parameters_dir(module)?)
+}
+
+/// Whether the parameter can be changed at runtime.
+pub fn is_writable(module: &str, param: &str) -> Result<bool> {
+    let metadata = std::fs::metadata(path(module, param)?).map_err(SysfsError::from_io)?;
+    Ok(metadata.permissions().mode() & 0o222 != 0)
+}
+
+/// Reads a parameter as text, with the trailing newline removed.
+pub fn read(module: &str, param: &str) -> Result<String> {
+    sysfs_read_text(&path(module, param)?)
+}
+
+/// Reads a parameter and parses it with [`FromStr`].
+pub fn read_as<T: FromStr>(module: &str, param: &str) -> Result<T> {
+    parse::value(&read(module, param)?)
+}
+
+/// Reads a boolean parameter, which the kernel shows as `Y` or `N`.
+pub fn read_bool(module: &str, param: &str) -> Result<bool> {
+    let text = read(module, param)?;
+    parse_bool(&text).ok_or_else(|| parse::ParseError::new("Y or N", text).into())
+}
+
+/// Writes a parameter as text, after checking that it is writable.
+pub fn write(module: &str, param: &str, value: impl AsRef<str>) -> Result<()> {
+    if !is_writable(module, param)? {
+        return Err(SysfsError::ReadOnlyAttribute);
+    }
+    sysfs_write(&path(module, param)?, value)
+}
+
+/// Writes a parameter formatted with [`fmt::Display`], after checking that
+/// it is writable.
+pub fn write_as<T: fmt::Display>(module: &str, param: &str, value: T) -> Result<()> {
+    write(module, param, value.to_string())
+}
+
+/// Writes a boolean parameter, after checking that it is writable.
+pub fn write_bool(module: &str, param: &str, value: bool) -> Result<()> {
+    write(module, param, if value { "Y" } else { "N" })
+}
+
+/// Parses the ways the kernel accepts booleans for parameters, of which it
+/// shows `Y` and `N`.
+fn parse_bool(text: &str) -> Option<bool> {
+    match text {
+        "Y" | "y" | "1" => Some(true),
+        "N" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_parameter_bools() {
+        assert_eq!(parse_bool("Y"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("-1"), None);
+    }
+
+    #[test]
+    fn module_parameter_paths() {
+        assert_eq!(
+            path("snd_hda_intel", "power_save").unwrap(),
+            "/sys/module/snd_hda_intel/parameters/power_save"
+        );
+        for (module, param) in [
+            ("", "power_save"),
+            ("..", "power_save"),
+            ("snd_hda_intel", "."),
+            ("snd_hda_intel", "../initstate"),
+        ] {
+            assert!(matches!(
+                path(module, param),
+                Err(SysfsError::InvalidValue(_))
+            ));
+        }
+    }
+}
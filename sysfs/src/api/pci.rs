@@ -0,0 +1,284 @@
+//! <https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-bus-pci>
+//!
+//! PCI devices are identified by their address, such as `0000:00:1f.3`.
+//!
+//! Active State Power Management (ASPM) lets PCIe links enter low power
+//! states while idle. The kernel applies a global [`aspm`] policy, which can
+//! be refined per device through the [`device`] `link` attributes. Runtime
+//! power management suspends idle devices entirely, and is enabled per
+//! device with [`device::control`].
+use std::fmt;
+use std::str::FromStr;
+
+use super::runtime_pm::RuntimePm;
+use crate::lib::{sysfs_attrs, sysfs_list, Result, SysfsError};
+
+/// The global ASPM policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AspmPolicy {
+    /// Use the settings configured by the firmware.
+    Default,
+    /// Disable ASPM on all links.
+    Performance,
+    /// Enable L0s and L1 on all links that support them.
+    Powersave,
+    /// Like `Powersave`, but also enable the L1 substates.
+    Powersupersave,
+}
+
+impl AspmPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Performance => "performance",
+            Self::Powersave => "powersave",
+            Self::Powersupersave => "powersupersave",
+        }
+    }
+}
+
+impl FromStr for AspmPolicy {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "default" => Ok(Self::Default),
+            "performance" => Ok(Self::Performance),
+            "powersave" => Ok(Self::Powersave),
+            "powersupersave" => Ok(Self::Powersupersave),
+            other => Err(format!("unknown ASPM policy: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for AspmPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A PCI class code, `0xCCSSPP` for the base class, subclass and
+/// programming interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Class(pub u32);
+
+impl Class {
+    pub const STORAGE: u8 = 0x01;
+    pub const NETWORK: u8 = 0x02;
+    pub const DISPLAY: u8 = 0x03;
+    pub const MULTIMEDIA: u8 = 0x04;
+    pub const BRIDGE: u8 = 0x06;
+    pub const SERIAL_BUS: u8 = 0x0c;
+
+    pub fn base(&self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    pub fn subclass(&self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    pub fn prog_if(&self) -> u8 {
+        self.0 as u8
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#08x}", self.0)
+    }
+}
+
+/// Selects devices by their [`Class`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClassMatch {
+    /// Devices of a base class, such as [`Class::NETWORK`].
+    Base(u8),
+    /// Devices of a base class and subclass, such as `(0x0c, 0x03)` for
+    /// USB controllers.
+    Subclass(u8, u8),
+    /// Devices of exactly this class.
+    Exact(Class),
+}
+
+impl ClassMatch {
+    pub fn matches(&self, class: Class) -> bool {
+        match *self {
+            Self::Base(base) => class.base() == base,
+            Self::Subclass(base, subclass) => class.base() == base && class.subclass() == subclass,
+            Self::Exact(exact) => class == exact,
+        }
+    }
+}
+
+/// An allow list and a deny list of device classes.
+///
+/// A class is selected if the allow list is empty or contains a match for
+/// it, and the deny list contains no match for it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClassFilter {
+    pub allow: Vec<ClassMatch>,
+    pub deny: Vec<ClassMatch>,
+}
+
+impl ClassFilter {
+    pub fn selects(&self, class: Class) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|m| m.matches(class)))
+            && !self.deny.iter().any(|m| m.matches(class))
+    }
+}
+
+/// The addresses of all PCI devices.
+pub fn devices() -> Result<Vec<String>> {
+    sysfs_list("/sys/bus/pci/devices")
+}
+
+/// The addresses of the PCI devices whose class is selected by `filter`.
+pub fn find_devices(filter: &ClassFilter) -> Result<Vec<String>> {
+    let mut found = devices()?;
+    found.retain(|address| matches!(device::class(address), Ok(class) if filter.selects(class)));
+    Ok(found)
+}
+
+/// Sets the runtime power management of every device selected by
+/// `filter`. Devices the kernel refused to change are returned together
+/// with the error.
+pub fn set_runtime_pm(
+    filter: &ClassFilter,
+    control: RuntimePm,
+) -> Result<Vec<(String, SysfsError)>> {
+    let mut refused = Vec::new();
+    for address in find_devices(filter)? {
+        if let Err(e) = device::set_control(&address, control) {
+            refused.push((address, e));
+        }
+    }
+    Ok(refused)
+}
+
+/// The global ASPM policy. Writing it fails if the firmware did not grant
+/// the kernel control over ASPM.
+#[sysfs_attrs(in "/sys/module/pcie_aspm/parameters")]
+pub mod aspm {
+    use super::AspmPolicy;
+    use crate::lib::{sysfs, Choice};
+
+    /// The available policies, with the one in use selected.
+    #[sysfs]
+    pub fn policy() -> Choice<AspmPolicy> {
+        ..
+    }
+}
+
+/// Attributes of a PCI device.
+#[sysfs_attrs(in "/sys/bus/pci/devices/{address}")]
+pub mod device {
+    use super::{Class, RuntimePm};
+    use crate::lib::parse::{self, hex};
+    use crate::lib::sysfs;
+
+    #[sysfs]
+    pub fn vendor(address: &str) -> u16 {
+        let try_read = parse::hex_as;
+        ..
+    }
+
+    #[sysfs(sysfs_file = "device")]
+    pub fn device_id(address: &str) -> u16 {
+        let try_read = parse::hex_as;
+        ..
+    }
+
+    #[sysfs]
+    pub fn class(address: &str) -> Class {
+        let try_read = |text: &str| hex(text).map(Class);
+        ..
+    }
+
+    /// Whether the device may be suspended at runtime while idle.
+    #[sysfs(in "./power")]
+    pub fn control(address: &str) -> RuntimePm {
+        let try_read = parse::value;
+        let write = |control: RuntimePm| control.as_str().to_owned();
+        ..
+    }
+
+    /// The runtime power state of the device, such as `active` or
+    /// `suspended`.
+    #[sysfs(in "./power")]
+    pub fn runtime_status(address: &str) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Whether the L0s state is enabled on the link of the device.
+    #[sysfs(in "./link")]
+    pub fn l0s_aspm(address: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+
+    /// Whether the L1 state is enabled on the link of the device.
+    #[sysfs(in "./link")]
+    pub fn l1_aspm(address: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+
+    /// Whether the L1.1 substate is enabled on the link of the device.
+    #[sysfs(in "./link")]
+    pub fn l1_1_aspm(address: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+
+    /// Whether the L1.2 substate is enabled on the link of the device.
+    #[sysfs(in "./link")]
+    pub fn l1_2_aspm(address: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+
+    /// Whether clock power management is enabled on the link of the
+    /// device.
+    #[sysfs(in "./link")]
+    pub fn clkpm(address: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_filter_allow_and_deny() {
+        let ethernet = Class(0x020000);
+        let xhci = Class(0x0c0330);
+        let vga = Class(0x030000);
+
+        let filter = ClassFilter {
+            allow: vec![],
+            deny: vec![ClassMatch::Base(Class::NETWORK)],
+        };
+        assert!(!filter.selects(ethernet));
+        assert!(filter.selects(xhci));
+
+        let filter = ClassFilter {
+            allow: vec![
+                ClassMatch::Subclass(Class::SERIAL_BUS, 0x03),
+                ClassMatch::Exact(vga),
+            ],
+            deny: vec![],
+        };
+        assert!(filter.selects(xhci));
+        assert!(filter.selects(vga));
+        assert!(!filter.selects(ethernet));
+    }
+}
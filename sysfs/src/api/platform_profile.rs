@@ -0,0 +1,203 @@
+//! <https://www.kernel.org/doc/html/latest/userspace-api/sysfs-platform_profile.html>
+//!
+//! Platform profiles select a platform level power/performance mode, which
+//! firmware uses to tune fan curves and power limits.
+//!
+//! Drivers register a class device under `/sys/class/platform-profile` for
+//! every profile handler, see [`class`]. The legacy interface in [`acpi`]
+//! only offers the profiles supported by all handlers, and writing to it
+//! changes all of them.
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::lib::{sysfs_attrs, Result, SysfsError};
+
+/// A platform profile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// Low power consumption.
+    LowPower,
+    /// Cooler operation.
+    Cool,
+    /// Quieter operation.
+    Quiet,
+    /// Balance between low power consumption and performance.
+    Balanced,
+    /// Balance between performance and low power consumption with a slight
+    /// bias towards performance.
+    BalancedPerformance,
+    /// High performance operation.
+    Performance,
+    /// Higher performance operation that may exceed internal battery draw
+    /// limits when on AC power.
+    MaxPower,
+    /// Driver defined custom profile, shown when the handlers of the legacy
+    /// interface are not all in the same profile.
+    Custom,
+    /// A profile unknown to this crate.
+    Other(String),
+}
+
+impl Profile {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::LowPower => "low-power",
+            Self::Cool => "cool",
+            Self::Quiet => "quiet",
+            Self::Balanced => "balanced",
+            Self::BalancedPerformance => "balanced-performance",
+            Self::Performance => "performance",
+            Self::MaxPower => "max-power",
+            Self::Custom => "custom",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+impl FromStr for Profile {
+    type Err = Infallible;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match text {
+            "low-power" => Self::LowPower,
+            "cool" => Self::Cool,
+            "quiet" => Self::Quiet,
+            "balanced" => Self::Balanced,
+            "balanced-performance" => Self::BalancedPerformance,
+            "performance" => Self::Performance,
+            "max-power" => Self::MaxPower,
+            "custom" => Self::Custom,
+            other => Self::Other(other.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn check_choice(profile: &Profile, choices: &[Profile]) -> Result<()> {
+    if choices.contains(profile) {
+        Ok(())
+    } else {
+        Err(SysfsError::InvalidValue(format!(
+            "the platform profile `{profile}` is not one of the choices"
+        )))
+    }
+}
+
+/// Selects `profile` on every profile handler.
+///
+/// This goes through the legacy interface when it exists, and otherwise
+/// writes to every class device that offers `profile`. An error is returned
+/// if no handler offers it.
+pub fn select(profile: Profile) -> Result<()> {
+    match acpi::select(profile.clone()) {
+        Err(SysfsError::MissingAttribute) => {}
+        result => return result,
+    }
+
+    let mut selected = false;
+    for device in class::devices()? {
+        if class::choices(device)?.contains(&profile) {
+            class::set_profile(device, profile.clone())?;
+            selected = true;
+        }
+    }
+    if selected {
+        Ok(())
+    } else {
+        Err(SysfsError::InvalidValue(format!(
+            "no platform profile handler offers `{profile}`"
+        )))
+    }
+}
+
+/// The legacy interface under `/sys/firmware/acpi`.
+#[sysfs_attrs(in "/sys/firmware/acpi")]
+pub mod acpi {
+    use super::{check_choice, Profile};
+    use crate::lib::{sysfs, Result};
+
+    /// The platform profiles supported by all profile handlers.
+    #[sysfs]
+    pub fn platform_profile_choices() -> Vec<Profile> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(|p| p.parse().unwrap())
+                .collect()
+        };
+        ..
+    }
+
+    /// The currently selected platform profile.
+    ///
+    /// This attribute is read-write, and writing one of the
+    /// [`platform_profile_choices`] selects it on all profile handlers.
+    /// Reading returns [`Profile::Custom`] if the handlers disagree.
+    #[sysfs]
+    pub fn platform_profile() -> Profile {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |profile: Profile| profile.as_str().to_owned();
+        ..
+    }
+
+    /// Selects `profile` after checking that it is one of the
+    /// [`platform_profile_choices`].
+    pub fn select(profile: Profile) -> Result<()> {
+        check_choice(&profile, &platform_profile_choices()?)?;
+        set_platform_profile(profile)
+    }
+}
+
+/// The class devices under `/sys/class/platform-profile`, one for every
+/// profile handler. These were added in Linux 6.14.
+#[sysfs_attrs(in "/sys/class/platform-profile/platform-profile-{device}")]
+pub mod class {
+    use super::{check_choice, Profile};
+    use crate::lib::{sysfs, sysfs_list_indexed, Result};
+
+    /// The numbers of the registered `platform-profile-N` class devices.
+    pub fn devices() -> Result<Vec<usize>> {
+        sysfs_list_indexed("/sys/class/platform-profile", "platform-profile-")
+    }
+
+    /// The name of the driver that registered this profile handler.
+    #[sysfs]
+    pub fn name(device: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// The platform profiles supported by this profile handler.
+    #[sysfs]
+    pub fn choices(device: usize) -> Vec<Profile> {
+        let read = |text: &str| {
+            text.split_whitespace()
+                .map(|p| p.parse().unwrap())
+                .collect()
+        };
+        ..
+    }
+
+    /// The currently selected platform profile of this profile handler.
+    ///
+    /// This attribute is read-write, and writing one of the [`choices`]
+    /// selects it.
+    #[sysfs]
+    pub fn profile(device: usize) -> Profile {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |profile: Profile| profile.as_str().to_owned();
+        ..
+    }
+
+    /// Selects `profile` for this profile handler after checking that it is
+    /// one of the [`choices`].
+    pub fn select(device: usize, profile: Profile) -> Result<()> {
+        check_choice(&profile, &choices(device)?)?;
+        set_profile(device, profile)
+    }
+}
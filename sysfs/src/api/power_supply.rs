@@ -0,0 +1,451 @@
+//! <https://www.kernel.org/doc/html/latest/power/power_supply_class.html>
+//!
+//! Power supplies are identified by their directory name under
+//! `/sys/class/power_supply`, such as `AC` or `BAT0`.
+//!
+//! Batteries report their state either in energy (`energy_*` in µWh, with
+//! `power_now` in µW) or in charge (`charge_*` in µAh, with `current_now`
+//! in µA), depending on the driver. [`time_to_empty`] and [`time_to_full`]
+//! handle both.
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::lib::{sysfs_attrs, sysfs_list, Result, SysfsError};
+
+/// The kind of a power supply.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SupplyType {
+    Unknown,
+    Battery,
+    Ups,
+    Mains,
+    Usb,
+    /// A USB dedicated charging port.
+    UsbDcp,
+    /// A USB charging downstream port, which also carries data.
+    UsbCdp,
+    /// A USB accessory charger adapter.
+    UsbAca,
+    UsbC,
+    /// USB Power Delivery.
+    UsbPd,
+    /// USB Power Delivery on a dual-role port, which can be a source or a
+    /// sink.
+    UsbPdDrp,
+    /// An Apple Brick ID charger.
+    BrickId,
+    Wireless,
+    /// A type unknown to this crate.
+    Other(String),
+}
+
+impl SupplyType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Unknown => "Unknown",
+            Self::Battery => "Battery",
+            Self::Ups => "UPS",
+            Self::Mains => "Mains",
+            Self::Usb => "USB",
+            Self::UsbDcp => "USB_DCP",
+            Self::UsbCdp => "USB_CDP",
+            Self::UsbAca => "USB_ACA",
+            Self::UsbC => "USB_C",
+            Self::UsbPd => "USB_PD",
+            Self::UsbPdDrp => "USB_PD_DRP",
+            Self::BrickId => "BrickID",
+            Self::Wireless => "Wireless",
+            Self::Other(name) => name,
+        }
+    }
+
+    /// Whether the supply is any kind of USB port or charger.
+    pub fn is_usb(&self) -> bool {
+        self.as_str().starts_with("USB")
+    }
+}
+
+impl FromStr for SupplyType {
+    type Err = Infallible;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match text {
+            "Unknown" => Self::Unknown,
+            "Battery" => Self::Battery,
+            "UPS" => Self::Ups,
+            "Mains" => Self::Mains,
+            "USB" => Self::Usb,
+            "USB_DCP" => Self::UsbDcp,
+            "USB_CDP" => Self::UsbCdp,
+            "USB_ACA" => Self::UsbAca,
+            "USB_C" => Self::UsbC,
+            "USB_PD" => Self::UsbPd,
+            "USB_PD_DRP" => Self::UsbPdDrp,
+            "BrickID" => Self::BrickId,
+            "Wireless" => Self::Wireless,
+            other => Self::Other(other.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for SupplyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The charging status of a battery.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Status {
+    Unknown,
+    Charging,
+    Discharging,
+    /// Connected to a power source, but not charging, for example because
+    /// of a charge threshold.
+    NotCharging,
+    Full,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "Unknown",
+            Self::Charging => "Charging",
+            Self::Discharging => "Discharging",
+            Self::NotCharging => "Not charging",
+            Self::Full => "Full",
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "Unknown" => Ok(Self::Unknown),
+            "Charging" => Ok(Self::Charging),
+            "Discharging" => Ok(Self::Discharging),
+            "Not charging" => Ok(Self::NotCharging),
+            "Full" => Ok(Self::Full),
+            _ => Err(format!("unknown power supply status: {text}")),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How a battery is charged while connected to a power source.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChargeBehaviour {
+    /// Charge normally, respecting thresholds.
+    Auto,
+    /// Do not charge while AC is attached.
+    InhibitCharge,
+    /// Force discharge while AC is attached.
+    ForceDischarge,
+    /// A behaviour unknown to this crate, such as `inhibit-charge-awake`.
+    Other(String),
+}
+
+impl ChargeBehaviour {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Auto => "auto",
+            Self::InhibitCharge => "inhibit-charge",
+            Self::ForceDischarge => "force-discharge",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+impl FromStr for ChargeBehaviour {
+    type Err = Infallible;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match text {
+            "auto" => Self::Auto,
+            "inhibit-charge" => Self::InhibitCharge,
+            "force-discharge" => Self::ForceDischarge,
+            other => Self::Other(other.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for ChargeBehaviour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The names of all power supplies.
+pub fn supplies() -> Result<Vec<String>> {
+    sysfs_list("/sys/class/power_supply")
+}
+
+/// The names of the power supplies of the given type.
+pub fn find_supplies(kind: SupplyType) -> Result<Vec<String>> {
+    let mut found = supplies()?;
+    found.retain(|name| matches!(supply::kind(name), Ok(found) if found == kind));
+    Ok(found)
+}
+
+/// Whether the system is running from an external power source, that is,
+/// whether any mains or USB supply is online.
+///
+/// Systems without any such supply, like most desktops, are considered to
+/// be on external power.
+pub fn on_external_power() -> Result<bool> {
+    let mut external = supplies()?
+        .into_iter()
+        .filter(|name| {
+            matches!(supply::kind(name), Ok(kind) if kind == SupplyType::Mains || kind.is_usb())
+        })
+        .peekable();
+    if external.peek().is_none() {
+        return Ok(true);
+    }
+    for name in external {
+        if supply::online(&name)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The remaining and full level of a battery, and the rate it changes at,
+/// in matching units (µWh and µW, or µAh and µA).
+struct Level {
+    now: f64,
+    full: f64,
+    rate: f64,
+}
+
+fn optional<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Err(SysfsError::MissingAttribute) => Ok(None),
+        result => result.map(Some),
+    }
+}
+
+fn level(name: &str) -> Result<Level> {
+    if let Some(now) = optional(supply::energy_now(name))? {
+        let rate = match optional(supply::power_now(name))? {
+            Some(power) => power.unsigned_abs() as f64,
+            // Derive the power from voltage and current, in µV and µA.
+            None => {
+                supply::voltage_now(name)? as f64 * supply::current_now(name)?.unsigned_abs() as f64
+                    / 1e6
+            }
+        };
+        Ok(Level {
+            now: now as f64,
+            full: supply::energy_full(name)? as f64,
+            rate,
+        })
+    } else {
+        Ok(Level {
+            now: supply::charge_now(name)? as f64,
+            full: supply::charge_full(name)? as f64,
+            rate: supply::current_now(name)?.unsigned_abs() as f64,
+        })
+    }
+}
+
+/// The estimated time until a discharging battery is empty, at the current
+/// rate of discharge.
+///
+/// Returns `None` if the battery is not discharging, or reports no rate.
+pub fn time_to_empty(name: &str) -> Result<Option<Duration>> {
+    if supply::status(name)? != Status::Discharging {
+        return Ok(None);
+    }
+    let level = level(name)?;
+    Ok((level.rate > 0.0).then(|| Duration::from_secs_f64(level.now / level.rate * 3600.0)))
+}
+
+/// The estimated time until a charging battery is full, at the current
+/// rate of charge.
+///
+/// Returns `None` if the battery is not charging, or reports no rate.
+pub fn time_to_full(name: &str) -> Result<Option<Duration>> {
+    if supply::status(name)? != Status::Charging {
+        return Ok(None);
+    }
+    let level = level(name)?;
+    let remaining = (level.full - level.now).max(0.0);
+    Ok((level.rate > 0.0).then(|| Duration::from_secs_f64(remaining / level.rate * 3600.0)))
+}
+
+/// Sets the charge thresholds of a battery, in percent, so that charging
+/// starts below `start` and stops at `end`.
+///
+/// The thresholds are written in the order that keeps the start below the
+/// end at every step, since drivers reject writes that would invert them.
+/// Batteries that only support an end threshold accept `None` as `start`.
+pub fn set_charge_thresholds(name: &str, start: Option<u8>, end: u8) -> Result<()> {
+    if end > 100 {
+        return Err(SysfsError::InvalidValue(format!(
+            "the charge end threshold {end}% is above 100%"
+        )));
+    }
+    let Some(start) = start else {
+        return supply::set_charge_control_end_threshold(name, end);
+    };
+    if start >= end {
+        return Err(SysfsError::InvalidValue(format!(
+            "the charge start threshold {start}% is not below the end threshold {end}%"
+        )));
+    }
+
+    // Raising the start above the current end would invert them, so the
+    // end goes first in that case.
+    if start >= supply::charge_control_end_threshold(name)? {
+        supply::set_charge_control_end_threshold(name, end)?;
+        supply::set_charge_control_start_threshold(name, start)
+    } else {
+        supply::set_charge_control_start_threshold(name, start)?;
+        supply::set_charge_control_end_threshold(name, end)
+    }
+}
+
+/// Attributes of a power supply. Which attributes are present depends on
+/// the type of the supply and its driver.
+#[sysfs_attrs(in "/sys/class/power_supply/{name}")]
+pub mod supply {
+    use super::{ChargeBehaviour, Status, SupplyType};
+    use crate::lib::{sysfs, Choice};
+
+    /// Describes the main type of the supply.
+    #[sysfs(sysfs_file = "type")]
+    pub fn kind(name: &str) -> SupplyType {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Whether an external supply is connected, or for batteries, whether
+    /// the battery is present.
+    #[sysfs]
+    pub fn online(name: &str) -> bool {
+        let read = |text: &str| text != "0";
+        ..
+    }
+
+    /// The charging status of a battery.
+    #[sysfs]
+    pub fn status(name: &str) -> Status {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Fine grain representation of the battery capacity, in percent.
+    #[sysfs]
+    pub fn capacity(name: &str) -> u8 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Remaining energy, in µWh.
+    #[sysfs]
+    pub fn energy_now(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Energy of a full battery, in µWh.
+    #[sysfs]
+    pub fn energy_full(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Energy of a full battery as designed, in µWh.
+    #[sysfs]
+    pub fn energy_full_design(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Remaining charge, in µAh.
+    #[sysfs]
+    pub fn charge_now(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Charge of a full battery, in µAh.
+    #[sysfs]
+    pub fn charge_full(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Charge of a full battery as designed, in µAh.
+    #[sysfs]
+    pub fn charge_full_design(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Instantaneous power, in µW. Some drivers report a negative value
+    /// while discharging.
+    #[sysfs]
+    pub fn power_now(name: &str) -> i64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Instantaneous voltage, in µV.
+    #[sysfs]
+    pub fn voltage_now(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Instantaneous current, in µA. Some drivers report a negative value
+    /// while discharging.
+    #[sysfs]
+    pub fn current_now(name: &str) -> i64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The number of charge/discharge cycles the battery has experienced.
+    #[sysfs]
+    pub fn cycle_count(name: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Represents a battery percentage level, below which charging will
+    /// begin. Use [`super::set_charge_thresholds`] to change both
+    /// thresholds in a valid order.
+    #[sysfs]
+    pub fn charge_control_start_threshold(name: &str) -> u8 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: u8| format!("{percent}");
+        ..
+    }
+
+    /// Represents a battery percentage level, above which charging will
+    /// stop. Use [`super::set_charge_thresholds`] to change both thresholds
+    /// in a valid order.
+    #[sysfs]
+    pub fn charge_control_end_threshold(name: &str) -> u8 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: u8| format!("{percent}");
+        ..
+    }
+
+    /// The current charge behaviour, and the ones the battery supports.
+    #[sysfs]
+    pub fn charge_behaviour(name: &str) -> Choice<ChargeBehaviour> {
+        ..
+    }
+}
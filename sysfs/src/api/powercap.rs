@@ -0,0 +1,364 @@
+//! <https://www.kernel.org/doc/html/latest/power/powercap/powercap.html>
+//!
+//! The power capping framework exposes power zones in a tree, for example
+//! the Intel RAPL package zone `intel-rapl:0` with its `core` and `uncore`
+//! subzones `intel-rapl:0:0` and `intel-rapl:0:1`. Recent kernels use the
+//! same interface for AMD processors.
+//!
+//! Zones are identified by their directory name under
+//! `/sys/class/powercap`.
+use std::time::{Duration, Instant};
+
+use crate::lib::{sysfs_attrs, sysfs_list, Result, SysfsError};
+
+/// A power zone and its subzones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Zone {
+    /// The directory name of the zone, such as `intel-rapl:0`.
+    pub id: String,
+    /// The name of the zone, such as `package-0`, `core` or `uncore`.
+    pub name: String,
+    pub constraints: Vec<Constraint>,
+    pub zones: Vec<Zone>,
+}
+
+/// A power limit of a zone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    /// The `N` in the `constraint_N_*` attributes.
+    pub index: usize,
+    /// The name of the constraint, such as `long_term` or `short_term`.
+    pub name: String,
+}
+
+/// The names of the control types, such as `intel-rapl` and
+/// `intel-rapl-mmio`.
+pub fn control_types() -> Result<Vec<String>> {
+    let mut names = sysfs_list("/sys/class/powercap")?;
+    names.retain(|name| !name.contains(':'));
+    Ok(names)
+}
+
+/// Discovers all power zones, as a tree of the top-level zones.
+pub fn zones() -> Result<Vec<Zone>> {
+    let mut ids = sysfs_list("/sys/class/powercap")?;
+    ids.retain(|id| id.contains(':'));
+    // Build the deepest zones first, so that every zone can collect its
+    // finished subzones when it is built.
+    ids.sort_by_key(|id| id.matches(':').count());
+
+    let mut zones = Vec::<Zone>::new();
+    while let Some(id) = ids.pop() {
+        let mut zone = Zone {
+            name: zone::name(&id)?,
+            constraints: constraints(&id)?,
+            zones: Vec::new(),
+            id,
+        };
+        let (children, rest) = zones
+            .into_iter()
+            .partition(|child| parent_id(&child.id) == Some(&zone.id));
+        zone.zones = children;
+        zone.zones.sort_by(|a, b| a.id.cmp(&b.id));
+        zones = rest;
+        zones.push(zone);
+    }
+    zones.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(zones)
+}
+
+fn parent_id(id: &str) -> Option<&str> {
+    id.rsplit_once(':')
+        .map(|(parent, _)| parent)
+        .filter(|parent| parent.contains(':'))
+}
+
+/// The constraints of a zone, ordered by index.
+pub fn constraints(zone: &str) -> Result<Vec<Constraint>> {
+    sysfs_list(&format!("/sys/class/powercap/{zone}"))?
+        .iter()
+        .filter_map(|file| {
+            file.strip_prefix("constraint_")?
+                .strip_suffix("_name")?
+                .parse()
+                .ok()
+        })
+        .map(|index| {
+            Ok(Constraint {
+                index,
+                name: zone::constraint_name(zone, index)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|mut constraints| {
+            constraints.sort_by_key(|constraint| constraint.index);
+            constraints
+        })
+}
+
+/// Finds the index of the constraint called `name` in a zone.
+pub fn find_constraint(zone: &str, name: &str) -> Result<usize> {
+    constraints(zone)?
+        .into_iter()
+        .find(|constraint| constraint.name == name)
+        .map(|constraint| constraint.index)
+        .ok_or_else(|| {
+            SysfsError::InvalidValue(format!("the zone `{zone}` has no constraint `{name}`"))
+        })
+}
+
+/// Sets the power limit (in micro-watts) of the constraint called `name`,
+/// such as `long_term`, in a zone.
+pub fn set_power_limit(zone: &str, name: &str, limit: u64) -> Result<()> {
+    zone::set_constraint_power_limit_uw(zone, find_constraint(zone, name)?, limit)
+}
+
+/// Measures the average power of zones from their energy counters.
+///
+/// Every call to [`EnergyMeter::sample`] reports the energy used and the
+/// average power since the previous sample (or since creation), correcting
+/// for the counters wrapping around.
+#[derive(Clone, Debug)]
+pub struct EnergyMeter {
+    zones: Vec<MeteredZone>,
+    sampled_at: Instant,
+}
+
+#[derive(Clone, Debug)]
+struct MeteredZone {
+    id: String,
+    name: String,
+    max_energy_range_uj: u64,
+    energy_uj: u64,
+}
+
+/// The energy used by a zone over the interval between two samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerReading {
+    /// The directory name of the zone, such as `intel-rapl:0`.
+    pub zone: String,
+    /// The name of the zone, such as `package-0`.
+    pub name: String,
+    /// Energy used over the interval, in micro-joules.
+    pub energy_uj: u64,
+    pub interval: Duration,
+}
+
+impl PowerReading {
+    /// The average power over the interval, in watts.
+    pub fn watts(&self) -> f64 {
+        self.energy_uj as f64 / 1_000_000.0 / self.interval.as_secs_f64()
+    }
+}
+
+impl EnergyMeter {
+    /// Starts metering the zones with the given directory names.
+    pub fn new<I>(zones: I) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let zones = zones
+            .into_iter()
+            .map(|id| {
+                let id = id.into();
+                Ok(MeteredZone {
+                    name: zone::name(&id)?,
+                    max_energy_range_uj: zone::max_energy_range_uj(&id)?,
+                    energy_uj: zone::energy_uj(&id)?,
+                    id,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            zones,
+            sampled_at: Instant::now(),
+        })
+    }
+
+    /// Starts metering every zone in the tree that has an energy counter.
+    /// Zones without one, such as those of some control types, are skipped.
+    pub fn all() -> Result<Self> {
+        fn flatten(zones: Vec<Zone>, ids: &mut Vec<String>) {
+            for zone in zones {
+                if !matches!(zone::energy_uj(&zone.id), Err(SysfsError::MissingAttribute)) {
+                    ids.push(zone.id);
+                }
+                flatten(zone.zones, ids);
+            }
+        }
+        let mut ids = Vec::new();
+        flatten(zones()?, &mut ids);
+        Self::new(ids)
+    }
+
+    /// Reads the energy counters, and reports the energy used by each zone
+    /// since the previous sample.
+    pub fn sample(&mut self) -> Result<Vec<PowerReading>> {
+        let counters = self
+            .zones
+            .iter()
+            .map(|metered| zone::energy_uj(&metered.id))
+            .collect::<Result<Vec<_>>>()?;
+        let now = Instant::now();
+        let interval = now - self.sampled_at;
+        self.sampled_at = now;
+
+        Ok(self
+            .zones
+            .iter_mut()
+            .zip(counters)
+            .map(|(metered, energy_uj)| {
+                let used = energy_delta(metered.energy_uj, energy_uj, metered.max_energy_range_uj);
+                metered.energy_uj = energy_uj;
+                PowerReading {
+                    zone: metered.id.clone(),
+                    name: metered.name.clone(),
+                    energy_uj: used,
+                    interval,
+                }
+            })
+            .collect())
+    }
+
+    /// Restarts the interval, waits for `interval`, then takes a sample.
+    pub fn measure(&mut self, interval: Duration) -> Result<Vec<PowerReading>> {
+        self.sample()?;
+        std::thread::sleep(interval);
+        self.sample()
+    }
+}
+
+/// The difference between two readings of an energy counter that wraps
+/// around to zero after `max_range`. A `previous` reading above
+/// `max_range`, which a buggy driver may report, counts as `max_range`.
+pub fn energy_delta(previous: u64, current: u64, max_range: u64) -> u64 {
+    if current >= previous {
+        current - previous
+    } else {
+        max_range.saturating_sub(previous).saturating_add(current)
+    }
+}
+
+/// Attributes of a power zone. The `enabled` attribute is also present for
+/// control types.
+#[sysfs_attrs(in "/sys/class/powercap/{zone}")]
+pub mod zone {
+    use crate::lib::sysfs;
+
+    /// The name of the zone.
+    #[sysfs]
+    pub fn name(zone: &str) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Whether power capping is enabled for the zone, or for all zones of a
+    /// control type.
+    #[sysfs]
+    pub fn enabled(zone: &str) -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+
+    /// Current energy counter in micro-joules.
+    ///
+    /// The counter wraps around after [`max_energy_range_uj`].
+    #[sysfs]
+    pub fn energy_uj(zone: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The range of the energy counter in micro-joules.
+    #[sysfs]
+    pub fn max_energy_range_uj(zone: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Current power in micro-watts, for zones that can measure it.
+    #[sysfs]
+    pub fn power_uw(zone: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The range of the power counter in micro-watts.
+    #[sysfs]
+    pub fn max_power_range_uw(zone: &str) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Name of the constraint, such as `long_term`, `short_term` or
+    /// `peak_power`.
+    #[sysfs(sysfs_file = "constraint_{constraint}_name")]
+    pub fn constraint_name(zone: &str, constraint: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Power limit in micro-watts, which should be applicable for the time
+    /// window specified by [`constraint_time_window_us`].
+    #[sysfs(sysfs_file = "constraint_{constraint}_power_limit_uw")]
+    pub fn constraint_power_limit_uw(zone: &str, constraint: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |limit: u64| format!("{limit}");
+        ..
+    }
+
+    /// Time window in micro-seconds.
+    #[sysfs(sysfs_file = "constraint_{constraint}_time_window_us")]
+    pub fn constraint_time_window_us(zone: &str, constraint: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |window: u64| format!("{window}");
+        ..
+    }
+
+    /// Maximum allowed power in micro-watts.
+    #[sysfs(sysfs_file = "constraint_{constraint}_max_power_uw")]
+    pub fn constraint_max_power_uw(zone: &str, constraint: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Minimum allowed power in micro-watts.
+    #[sysfs(sysfs_file = "constraint_{constraint}_min_power_uw")]
+    pub fn constraint_min_power_uw(zone: &str, constraint: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Maximum allowed time window in micro-seconds.
+    #[sysfs(sysfs_file = "constraint_{constraint}_max_time_window_us")]
+    pub fn constraint_max_time_window_us(zone: &str, constraint: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Minimum allowed time window in micro-seconds.
+    #[sysfs(sysfs_file = "constraint_{constraint}_min_time_window_us")]
+    pub fn constraint_min_time_window_us(zone: &str, constraint: usize) -> u64 {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn energy_delta_wraps_around() {
+        assert_eq!(energy_delta(100, 250, 1_000), 150);
+        assert_eq!(energy_delta(900, 50, 1_000), 150);
+    }
+
+    #[test]
+    fn energy_delta_previous_above_range() {
+        assert_eq!(energy_delta(1_200, 50, 1_000), 50);
+        assert_eq!(energy_delta(u64::MAX, 50, u64::MAX), 50);
+    }
+}
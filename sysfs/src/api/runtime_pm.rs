@@ -0,0 +1,44 @@
+//! <https://www.kernel.org/doc/html/latest/power/runtime_pm.html>
+//!
+//! Runtime power management suspends idle devices while the system is
+//! running. It is controlled per device through `power/control`, for
+//! example by [`super::pci::device::control`] and
+//! [`super::usb::device::control`].
+use std::fmt;
+use std::str::FromStr;
+
+/// Whether a device is suspended at runtime while idle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RuntimePm {
+    /// The device may be suspended while idle.
+    Auto,
+    /// The device is kept powered on.
+    On,
+}
+
+impl RuntimePm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::On => "on",
+        }
+    }
+}
+
+impl FromStr for RuntimePm {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "auto" => Ok(Self::Auto),
+            "on" => Ok(Self::On),
+            other => Err(format!("unknown runtime PM control: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for RuntimePm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,156 @@
+//! <https://www.kernel.org/doc/html/latest/admin-guide/sysctl/index.html>
+//!
+//! Sysctls are identified by their dotted name, such as `vm.swappiness`,
+//! which maps to a file under `/proc/sys`. Like with sysctl(8), `/` may be
+//! used as the separator instead, for names whose components contain dots.
+use crate::lib::{sysfs_attrs, sysfs_read_text, sysfs_write, Result, SysfsError};
+
+/// The path of the file for the sysctl `name`.
+///
+/// Names that could lead outside of `/proc/sys`, because they are empty,
+/// start with `/` or have `.` or `..` components, are rejected with
+/// [`SysfsError::InvalidValue`].
+pub fn path(name: &str) -> Result<String> {
+    let relative = if name.contains('/') {
+        name.to_owned()
+    } else {
+        name.replace('.', "/")
+    };
+    if relative
+        .split('/')
+        .any(|component| matches!(component, "" | "." | ".."))
+    {
+        return Err(SysfsError::InvalidValue(format!(
+            "`{name}` is not a valid sysctl name"
+        )));
+    }
+    Ok(format!("/proc/sys/{relative}"))
+}
+
+/// Reads any sysctl as text, with the trailing newline removed.
+pub fn read(name: &str) -> Result<String> {
+    sysfs_read_text(&path(name)?)
+}
+
+/// Writes any sysctl as text. No validation of the value is performed.
+pub fn write(name: &str, value: impl AsRef<str>) -> Result<()> {
+    sysfs_write(&path(name)?, value)
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/sysctl/vm.html>
+#[sysfs_attrs(in "/proc/sys/vm")]
+pub mod vm {
+    use crate::lib::sysfs;
+
+    /// How aggressively anonymous memory is swapped out relative to the
+    /// page cache being dropped, from `0` to `200`.
+    #[sysfs]
+    pub fn swappiness() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |swappiness: u32| format!("{swappiness}");
+        ..
+    }
+
+    /// Delays writeback so the disk can stay spun down, and writes out
+    /// everything at once when it spins up. The value is the number of
+    /// seconds to wait after a read before writing, with `0` disabling
+    /// laptop mode.
+    #[sysfs]
+    pub fn laptop_mode() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |seconds: u32| format!("{seconds}");
+        ..
+    }
+
+    /// How often the writeback threads wake up to write dirty data, in
+    /// hundredths of a second. `0` disables periodic writeback.
+    #[sysfs]
+    pub fn dirty_writeback_centisecs() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |centisecs: u32| format!("{centisecs}");
+        ..
+    }
+
+    /// How old dirty data must be to be written out by the writeback
+    /// threads, in hundredths of a second.
+    #[sysfs]
+    pub fn dirty_expire_centisecs() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |centisecs: u32| format!("{centisecs}");
+        ..
+    }
+
+    /// The percentage of available memory that may be dirty before
+    /// processes writing are made to write it out themselves.
+    #[sysfs]
+    pub fn dirty_ratio() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: u32| format!("{percent}");
+        ..
+    }
+
+    /// The percentage of available memory that may be dirty before the
+    /// writeback threads start writing it out in the background.
+    #[sysfs]
+    pub fn dirty_background_ratio() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |percent: u32| format!("{percent}");
+        ..
+    }
+
+    /// How aggressively the caches of directory and inode objects are
+    /// reclaimed, relative to the page cache. The default is `100`.
+    #[sysfs]
+    pub fn vfs_cache_pressure() -> u32 {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |pressure: u32| format!("{pressure}");
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/admin-guide/sysctl/kernel.html>
+#[sysfs_attrs(in "/proc/sys/kernel")]
+pub mod kernel {
+    use crate::lib::sysfs;
+
+    /// Whether the NMI watchdog is enabled, which periodically wakes every
+    /// CPU to detect hard lockups.
+    #[sysfs]
+    pub fn nmi_watchdog() -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+
+    /// Whether tasks are grouped by session for scheduling, so a heavy
+    /// workload in one terminal does not starve the desktop.
+    #[sysfs]
+    pub fn sched_autogroup_enabled() -> bool {
+        let read = |text: &str| text != "0";
+        let write = |enabled: bool| format!("{}", enabled as u8);
+        ..
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sysctl_names_to_paths() {
+        assert_eq!(path("vm.swappiness").unwrap(), "/proc/sys/vm/swappiness");
+        assert_eq!(
+            path("net/ipv4/conf/eth0.1/forwarding").unwrap(),
+            "/proc/sys/net/ipv4/conf/eth0.1/forwarding"
+        );
+        for name in [
+            "",
+            "/etc/shadow",
+            "../../etc/shadow",
+            "vm/../../sys",
+            "vm..x",
+        ] {
+            assert!(matches!(path(name), Err(SysfsError::InvalidValue(_))));
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! <https://www.kernel.org/doc/html/latest/driver-api/thermal/sysfs-api.html>
+//!
+//! Thermal zones and cooling devices are identified by the number in their
+//! directory name, `thermal_zoneN` and `cooling_deviceN`.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::lib::{sysfs_attrs, sysfs_list_indexed, Result, SysfsError};
+
+/// A temperature in millidegrees Celsius, the unit used throughout the
+/// thermal interface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MilliCelsius(pub i64);
+
+impl MilliCelsius {
+    pub fn from_celsius(celsius: f64) -> Self {
+        Self((celsius * 1000.0).round() as i64)
+    }
+
+    pub fn as_celsius(&self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+
+    /// Whether the temperature is above absolute zero. Drivers register
+    /// trip points they do not use with `THERMAL_TEMP_INVALID`, which reads
+    /// as `-274000`.
+    pub fn is_valid(&self) -> bool {
+        self.0 > -273150
+    }
+}
+
+impl FromStr for MilliCelsius {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        text.parse().map(Self)
+    }
+}
+
+impl fmt::Display for MilliCelsius {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} °C", self.as_celsius())
+    }
+}
+
+/// Whether a thermal zone is being monitored by the kernel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Enabled,
+    Disabled,
+}
+
+impl Mode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Enabled => "enabled",
+            Self::Disabled => "disabled",
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "enabled" => Ok(Self::Enabled),
+            "disabled" => Ok(Self::Disabled),
+            _ => Err(format!("unknown thermal zone mode: {text}")),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What happens when a trip point is crossed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TripType {
+    /// Cooling devices such as fans are activated.
+    Active,
+    /// The kernel throttles devices to reduce the temperature.
+    Passive,
+    /// The platform is notified that it is getting too hot.
+    Hot,
+    /// The system is shut down.
+    Critical,
+}
+
+impl TripType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Passive => "passive",
+            Self::Hot => "hot",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for TripType {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "active" => Ok(Self::Active),
+            "passive" => Ok(Self::Passive),
+            "hot" => Ok(Self::Hot),
+            "critical" => Ok(Self::Critical),
+            _ => Err(format!("unknown trip point type: {text}")),
+        }
+    }
+}
+
+impl fmt::Display for TripType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A trip point of a thermal zone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TripPoint {
+    /// The `M` in the `trip_point_M_*` attributes.
+    pub index: usize,
+    pub kind: TripType,
+    pub temp: MilliCelsius,
+    /// The hysteresis, if the zone reports one.
+    pub hyst: Option<MilliCelsius>,
+}
+
+/// The numbers of all thermal zones.
+pub fn zones() -> Result<Vec<usize>> {
+    sysfs_list_indexed("/sys/class/thermal", "thermal_zone")
+}
+
+/// The numbers of the thermal zones of the given type, such as
+/// `x86_pkg_temp` or `acpitz`.
+pub fn find_zones(kind: &str) -> Result<Vec<usize>> {
+    let mut found = Vec::new();
+    for zone in zones()? {
+        if zone::kind(zone)? == kind {
+            found.push(zone);
+        }
+    }
+    Ok(found)
+}
+
+/// The numbers of all cooling devices.
+pub fn cooling_devices() -> Result<Vec<usize>> {
+    sysfs_list_indexed("/sys/class/thermal", "cooling_device")
+}
+
+/// The trip points of a thermal zone, ordered by index. Trip points with
+/// an invalid temperature are skipped.
+pub fn trip_points(zone: usize) -> Result<Vec<TripPoint>> {
+    let mut trip_points = Vec::new();
+    for index in 0.. {
+        let kind = match zone::trip_point_type(zone, index) {
+            Err(SysfsError::MissingAttribute) => break,
+            kind => kind?,
+        };
+        let hyst = match zone::trip_point_hyst(zone, index) {
+            Err(SysfsError::MissingAttribute) => None,
+            hyst => Some(hyst?),
+        };
+        let temp = zone::trip_point_temp(zone, index)?;
+        if temp.is_valid() {
+            trip_points.push(TripPoint {
+                index,
+                kind,
+                temp,
+                hyst,
+            });
+        }
+    }
+    Ok(trip_points)
+}
+
+/// How far the temperature of a thermal zone is below its lowest passive,
+/// hot or critical trip point, which is where the system starts being
+/// throttled or shut down.
+///
+/// Returns `None` if the zone has no such trip point.
+pub fn headroom(zone: usize) -> Result<Option<MilliCelsius>> {
+    let temp = zone::temp(zone)?;
+    Ok(throttle_limit(&trip_points(zone)?).map(|limit| MilliCelsius(limit.0 - temp.0)))
+}
+
+/// The lowest temperature of the valid passive, hot or critical trip
+/// points.
+fn throttle_limit(trip_points: &[TripPoint]) -> Option<MilliCelsius> {
+    trip_points
+        .iter()
+        .filter(|trip| trip.kind != TripType::Active && trip.temp.is_valid())
+        .map(|trip| trip.temp)
+        .min()
+}
+
+/// <https://www.kernel.org/doc/html/latest/driver-api/thermal/sysfs-api.html#thermal-zone-attributes>
+#[sysfs_attrs(in "/sys/class/thermal/thermal_zone{zone}")]
+pub mod zone {
+    use super::{MilliCelsius, Mode, TripType};
+    use crate::lib::sysfs;
+
+    /// Strings which represent the thermal zone type, such as
+    /// `x86_pkg_temp` or `acpitz`.
+    #[sysfs(sysfs_file = "type")]
+    pub fn kind(zone: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Current temperature as reported by thermal zone (sensor).
+    #[sysfs]
+    pub fn temp(zone: usize) -> MilliCelsius {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// Whether the thermal zone is monitored by the kernel. Disabling it
+    /// stops the kernel from reacting to trip points.
+    #[sysfs]
+    pub fn mode(zone: usize) -> Mode {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |mode: Mode| mode.as_str().to_owned();
+        ..
+    }
+
+    /// One of the various thermal governors used for a particular zone.
+    ///
+    /// This attribute is read-write, and writing one of the
+    /// [`available_policies`] selects it.
+    #[sysfs]
+    pub fn policy(zone: usize) -> String {
+        let read = str::to_owned;
+        let write = |policy: &str| policy.to_owned();
+        ..
+    }
+
+    /// Available thermal governors which can be used for a particular zone.
+    #[sysfs]
+    pub fn available_policies(zone: usize) -> Vec<String> {
+        let read = |text: &str| text.split_whitespace().map(str::to_owned).collect();
+        ..
+    }
+
+    /// The temperature above which trip point will be fired.
+    ///
+    /// This attribute is only writable if the kernel was built with
+    /// `CONFIG_THERMAL_WRITABLE_TRIPS`.
+    #[sysfs(sysfs_file = "trip_point_{trip}_temp")]
+    pub fn trip_point_temp(zone: usize, trip: usize) -> MilliCelsius {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |temp: MilliCelsius| format!("{}", temp.0);
+        ..
+    }
+
+    /// Strings which indicate the type of the trip point.
+    #[sysfs(sysfs_file = "trip_point_{trip}_type")]
+    pub fn trip_point_type(zone: usize, trip: usize) -> TripType {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The hysteresis value for a trip point.
+    #[sysfs(sysfs_file = "trip_point_{trip}_hyst")]
+    pub fn trip_point_hyst(zone: usize, trip: usize) -> MilliCelsius {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |hyst: MilliCelsius| format!("{}", hyst.0);
+        ..
+    }
+}
+
+/// <https://www.kernel.org/doc/html/latest/driver-api/thermal/sysfs-api.html#cooling-device-attributes>
+#[sysfs_attrs(in "/sys/class/thermal/cooling_device{device}")]
+pub mod cooling_device {
+    use crate::lib::sysfs;
+
+    /// String which represents the type of device, such as `Processor` or
+    /// `Fan`.
+    #[sysfs(sysfs_file = "type")]
+    pub fn kind(device: usize) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// The maximum cooling state of the cooling device.
+    #[sysfs]
+    pub fn max_state(device: usize) -> usize {
+        let read = |text: &str| text.parse().unwrap();
+        ..
+    }
+
+    /// The current cooling state of the cooling device. The value can be
+    /// any integer between 0 and [`max_state`], where 0 means no cooling.
+    #[sysfs]
+    pub fn cur_state(device: usize) -> usize {
+        let read = |text: &str| text.parse().unwrap();
+        let write = |state: usize| format!("{state}");
+        ..
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle_limit_skips_invalid_trips() {
+        let trip = |index, kind, temp| TripPoint {
+            index,
+            kind,
+            temp: MilliCelsius(temp),
+            hyst: None,
+        };
+        // iwlwifi registers its unused trips like this.
+        let trip_points = [
+            trip(0, TripType::Passive, -274000),
+            trip(1, TripType::Active, 60000),
+            trip(2, TripType::Passive, 95000),
+            trip(3, TripType::Critical, 105000),
+        ];
+        assert_eq!(throttle_limit(&trip_points), Some(MilliCelsius(95000)));
+        assert_eq!(throttle_limit(&trip_points[..2]), None);
+    }
+}
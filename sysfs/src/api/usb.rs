@@ -0,0 +1,238 @@
+//! <https://www.kernel.org/doc/html/latest/driver-api/usb/power-management.html>
+//!
+//! USB devices are identified by their name under `/sys/bus/usb/devices`,
+//! such as `usb1` for a root hub or `1-2.1` for a device behind a hub. Their
+//! interfaces are named after the device, the configuration and the
+//! interface number, such as `1-2.1:1.0`.
+use std::fmt;
+use std::str::FromStr;
+
+use super::runtime_pm::RuntimePm;
+use crate::lib::{sysfs_attrs, sysfs_list, Result, SysfsError};
+
+/// The interface class of human interface devices, such as keyboards and
+/// mice.
+pub const CLASS_HID: u8 = 0x03;
+
+/// The vendor and product ID of a USB device, written as `046d:c52b`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UsbId {
+    pub vendor: u16,
+    pub product: u16,
+}
+
+impl FromStr for UsbId {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |id: &str| u16::from_str_radix(id, 16).ok();
+        text.split_once(':')
+            .and_then(|(vendor, product)| {
+                Some(Self {
+                    vendor: parse(vendor)?,
+                    product: parse(product)?,
+                })
+            })
+            .ok_or_else(|| format!("expected VENDOR:PRODUCT, found {text}"))
+    }
+}
+
+impl fmt::Display for UsbId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}:{:04x}", self.vendor, self.product)
+    }
+}
+
+/// Devices that are never autosuspended, either by their ID or by the
+/// class of any of their interfaces.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DenyList {
+    pub ids: Vec<UsbId>,
+    pub interface_classes: Vec<u8>,
+}
+
+impl DenyList {
+    pub fn denies(&self, id: UsbId, interface_classes: &[u8]) -> bool {
+        self.ids.contains(&id)
+            || interface_classes
+                .iter()
+                .any(|class| self.interface_classes.contains(class))
+    }
+}
+
+/// The names of all USB devices, including root hubs, but not their
+/// interfaces.
+pub fn devices() -> Result<Vec<String>> {
+    let mut found = sysfs_list("/sys/bus/usb/devices")?;
+    found.retain(|name| !name.contains(':'));
+    Ok(found)
+}
+
+/// The names of the interfaces of a USB device.
+pub fn interfaces(device: &str) -> Result<Vec<String>> {
+    Ok(interface_entries(sysfs_list(&format!(
+        "/sys/bus/usb/devices/{device}"
+    ))?))
+}
+
+/// Picks the interfaces out of the entries of a device directory, which
+/// are the only ones with a `:` in their name. They cannot be found by the
+/// device name, since the interfaces of a root hub `usbN` are named after
+/// `N-0` instead.
+fn interface_entries(mut entries: Vec<String>) -> Vec<String> {
+    entries.retain(|name| name.contains(':'));
+    entries
+}
+
+/// The classes of the interfaces of a USB device.
+pub fn interface_classes(device: &str) -> Result<Vec<u8>> {
+    interfaces(device)?
+        .iter()
+        .map(|name| interface::class(name))
+        .collect()
+}
+
+/// Whether the device has an input interface, such as a keyboard or mouse.
+/// Suspending these makes them lose or delay input.
+pub fn is_input(device: &str) -> Result<bool> {
+    Ok(interface_classes(device)?.contains(&CLASS_HID))
+}
+
+/// Allows every device that is not denied to be suspended while idle,
+/// after `delay_ms` if given. Input devices are always left alone.
+///
+/// Devices that could not be inspected or changed are returned together
+/// with the error.
+pub fn set_autosuspend(
+    deny: &DenyList,
+    delay_ms: Option<i32>,
+) -> Result<Vec<(String, SysfsError)>> {
+    let mut refused = Vec::new();
+    for name in devices()? {
+        if let Err(e) = autosuspend(&name, deny, delay_ms) {
+            refused.push((name, e));
+        }
+    }
+    Ok(refused)
+}
+
+fn autosuspend(name: &str, deny: &DenyList, delay_ms: Option<i32>) -> Result<()> {
+    let classes = interface_classes(name)?;
+    if classes.contains(&CLASS_HID) || deny.denies(device::id(name)?, &classes) {
+        return Ok(());
+    }
+    if let Some(delay_ms) = delay_ms {
+        device::set_autosuspend_delay_ms(name, delay_ms)?;
+    }
+    device::set_control(name, RuntimePm::Auto)
+}
+
+/// Attributes of a USB device.
+#[sysfs_attrs(in "/sys/bus/usb/devices/{device}")]
+pub mod device {
+    use super::{RuntimePm, UsbId};
+    use crate::lib::{parse, sysfs, Result};
+
+    /// The vendor and product ID of the device.
+    pub fn id(device: &str) -> Result<UsbId> {
+        Ok(UsbId {
+            vendor: vendor(device)?,
+            product: product_id(device)?,
+        })
+    }
+
+    #[sysfs(sysfs_file = "idVendor")]
+    pub fn vendor(device: &str) -> u16 {
+        let try_read = parse::hex_as;
+        ..
+    }
+
+    #[sysfs(sysfs_file = "idProduct")]
+    pub fn product_id(device: &str) -> u16 {
+        let try_read = parse::hex_as;
+        ..
+    }
+
+    /// The name of the product, if the device reports one.
+    #[sysfs]
+    pub fn product(device: &str) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// The name of the manufacturer, if the device reports one.
+    #[sysfs]
+    pub fn manufacturer(device: &str) -> String {
+        let read = str::to_owned;
+        ..
+    }
+
+    /// Whether the device may be suspended at runtime while idle.
+    #[sysfs(in "./power")]
+    pub fn control(device: &str) -> RuntimePm {
+        let try_read = parse::value;
+        let write = |control: RuntimePm| control.as_str().to_owned();
+        ..
+    }
+
+    /// How long the device must be idle before it is suspended, in
+    /// milliseconds. Negative values prevent autosuspend.
+    #[sysfs(in "./power")]
+    pub fn autosuspend_delay_ms(device: &str) -> i32 {
+        let try_read = parse::value;
+        let write = |delay: i32| format!("{delay}");
+        ..
+    }
+
+    /// The runtime power state of the device, such as `active` or
+    /// `suspended`.
+    #[sysfs(in "./power")]
+    pub fn runtime_status(device: &str) -> String {
+        let read = str::to_owned;
+        ..
+    }
+}
+
+/// Attributes of an interface of a USB device.
+#[sysfs_attrs(in "/sys/bus/usb/devices/{interface}")]
+pub mod interface {
+    use crate::lib::{parse, sysfs};
+
+    /// The class of the interface, such as [`super::CLASS_HID`].
+    #[sysfs(sysfs_file = "bInterfaceClass")]
+    pub fn class(interface: &str) -> u8 {
+        let try_read = parse::hex_as;
+        ..
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deny_list_by_id_or_class() {
+        let receiver: UsbId = "046d:c52b".parse().unwrap();
+        assert_eq!(receiver.to_string(), "046d:c52b");
+        assert!("046d".parse::<UsbId>().is_err());
+
+        let deny = DenyList {
+            ids: vec![receiver],
+            interface_classes: vec![0xe0],
+        };
+        let bluetooth: UsbId = "8087:0026".parse().unwrap();
+        let ethernet: UsbId = "0bda:8153".parse().unwrap();
+        assert!(deny.denies(receiver, &[]));
+        assert!(deny.denies(bluetooth, &[0xe0]));
+        assert!(!deny.denies(ethernet, &[0xff]));
+    }
+
+    #[test]
+    fn root_hub_interfaces() {
+        let entries = ["1-0:1.0", "1-1", "bConfigurationValue", "power"];
+        assert_eq!(
+            interface_entries(entries.map(String::from).to_vec()),
+            ["1-0:1.0"]
+        );
+    }
+}
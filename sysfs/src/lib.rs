@@ -4,5 +4,18 @@ pub mod lib {
 }
 
 pub mod api {
+    pub mod block;
     pub mod cpu;
+    pub mod gpu;
+    pub mod hwmon;
+    pub mod mm;
+    pub mod module_params;
+    pub mod pci;
+    pub mod platform_profile;
+    pub mod power_supply;
+    pub mod powercap;
+    pub mod runtime_pm;
+    pub mod sysctl;
+    pub mod thermal;
+    pub mod usb;
 }
@@ -0,0 +1,93 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{parse, SysfsError};
+
+/// The value of an attribute that lists the values it accepts, with the
+/// current one in brackets, such as `[none] mq-deadline kyber`.
+///
+/// Writing such an attribute takes only the bare value, so a `#[sysfs]`
+/// function returning a `Choice<T>` generates a setter that takes a `T`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Choice<T> {
+    available: Vec<T>,
+    selected: usize,
+}
+
+impl<T> Choice<T> {
+    /// Returns `None` if `selected` is out of bounds for `available`.
+    pub fn new(available: Vec<T>, selected: usize) -> Option<Self> {
+        (selected < available.len()).then_some(Self {
+            available,
+            selected,
+        })
+    }
+
+    /// All of the values the attribute accepts, including the selected one.
+    pub fn available(&self) -> &[T] {
+        &self.available
+    }
+
+    /// The current value.
+    pub fn selected(&self) -> &T {
+        &self.available[self.selected]
+    }
+
+    /// The index of the current value in [`Choice::available`].
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn into_selected(mut self) -> T {
+        self.available.swap_remove(self.selected)
+    }
+
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.available.contains(value)
+    }
+}
+
+impl<T: FromStr> FromStr for Choice<T> {
+    type Err = SysfsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse::bracketed(s)
+    }
+}
+
+/// Formats in the same way the kernel does, with the selected value in
+/// brackets.
+impl<T: fmt::Display> fmt::Display for Choice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, value) in self.available.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            if index == self.selected {
+                write!(f, "[{value}]")?;
+            } else {
+                write!(f, "{value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choice_round_trip() {
+        let text = "always [madvise] never";
+        let choice: Choice<String> = text.parse().unwrap();
+        assert_eq!(choice.selected(), "madvise");
+        assert_eq!(choice.available(), ["always", "madvise", "never"]);
+        assert!(choice.contains(&"never".to_owned()));
+        assert_eq!(choice.to_string(), text);
+        assert_eq!(choice.into_selected(), "madvise");
+    }
+}
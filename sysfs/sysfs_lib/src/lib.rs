@@ -3,13 +3,19 @@
 //
 // If you see unchecked string functions being called,
 // it's because *sysfs* is guaranteed to be ASCII (where we expect text).
-
-// The maximum number of bytes that can be read from any given
-// *sysfs* attribute. Generally there should be nothing larger than this.
+//
+// The typed sysctl bindings read numbers from `/proc/sys`, which are ASCII
+// as well. Sysctls holding free text are read with `sysfs_read_text`.
 
 use std::fs::OpenOptions;
 use std::io::{ErrorKind, Read as _, Write as _};
 
+mod choice;
+pub mod parse;
+
+pub use choice::Choice;
+pub use parse::ParseError;
+
 pub type Result<T> = std::result::Result<T, SysfsError>;
 
 #[derive(Debug, thiserror::Error)]
@@ -21,12 +27,39 @@ pub enum SysfsError {
     /// Sometimes attributes are unsupported on a platform.
     #[error("the requested sysfs attribute is not supported on this platform")]
     UnsupportedAttribute,
+    /// The attribute was not written to, because its file mode does not
+    /// allow writing.
+    #[error("the requested sysfs attribute is read-only")]
+    ReadOnlyAttribute,
+    /// A value was rejected before writing it, because it is not one of
+    /// the values the attribute accepts.
+    #[error("invalid value for the sysfs attribute: {0}")]
+    InvalidValue(String),
+    /// The contents of the attribute are not in the expected format.
+    #[error("could not parse the sysfs attribute: {0}")]
+    Parse(#[from] ParseError),
 
     #[error("encountered IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
-pub const SYSFS_MAX_ATTR_BYTES: usize = 1024;
+impl SysfsError {
+    /// Wraps an IO error from accessing an attribute. A missing file means
+    /// [`SysfsError::MissingAttribute`].
+    pub fn from_io(e: std::io::Error) -> Self {
+        if e.kind() == ErrorKind::NotFound {
+            Self::MissingAttribute
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+/// The maximum number of bytes that can be read from any given *sysfs*
+/// attribute. The kernel formats attributes into a single page, so nothing
+/// is larger than this. Multi-line tables such as the `amdgpu`
+/// `pp_power_profile_mode` easily exceed a kilobyte.
+pub const SYSFS_MAX_ATTR_BYTES: usize = 4096;
 
 /// # Safety
 ///
@@ -40,8 +73,22 @@ pub const SYSFS_MAX_ATTR_BYTES: usize = 1024;
 /// `.unwrap()` when parsing the file content as an attribute value.
 ///
 /// It is undefined behavior to use this function with file paths not exposed
-/// through *sysfs*.
+/// through *sysfs*, other than those of numeric sysctls in `/proc/sys`.
 pub unsafe fn sysfs_read<T>(file_path: &str, parse_ok: fn(&str) -> T) -> Result<T> {
+    // SAFETY: The caller upholds the same contract.
+    unsafe { sysfs_try_read(file_path, |text| Ok(parse_ok(text))) }
+}
+
+/// The same as [`sysfs_read`], but `parse` may fail, for example with one of
+/// the parsers in [`parse`].
+///
+/// # Safety
+///
+/// See [`sysfs_read`].
+pub unsafe fn sysfs_try_read<T>(
+    file_path: &str,
+    parse: impl FnOnce(&str) -> Result<T>,
+) -> Result<T> {
     let mut buf = [0; SYSFS_MAX_ATTR_BYTES];
     let result = OpenOptions::new()
         .read(true)
@@ -56,9 +103,19 @@ pub unsafe fn sysfs_read<T>(file_path: &str, parse_ok: fn(&str) -> T) -> Result<
 
     match result {
         Ok("<unsupported>") => Err(SysfsError::UnsupportedAttribute),
-        Ok(text) => Ok(parse_ok(text)),
-        Err(e) if e.kind() == ErrorKind::NotFound => Err(SysfsError::MissingAttribute),
-        Err(e) => Err(SysfsError::from(e)),
+        Ok(text) => parse(text),
+        Err(e) => Err(SysfsError::from_io(e)),
+    }
+}
+
+/// Reads an attribute as text, with the trailing newline removed. Unlike
+/// [`sysfs_read`], the contents are checked to be valid UTF-8, so this is
+/// safe to use with any file, such as sysctls that hold free text.
+pub fn sysfs_read_text(file_path: &str) -> Result<String> {
+    let text = std::fs::read_to_string(file_path).map_err(SysfsError::from_io)?;
+    match text.trim_end() {
+        "<unsupported>" => Err(SysfsError::UnsupportedAttribute),
+        text => Ok(text.to_owned()),
     }
 }
 
@@ -72,11 +129,34 @@ pub fn sysfs_write(file_path: &str, value: impl AsRef<str>) -> Result<()> {
         .create(false)
         .open(file_path)
         .and_then(|mut f| write!(f, "{}", value.as_ref()))
-        .map_err(|e| {
-            if e.kind() == ErrorKind::NotFound {
-                SysfsError::MissingAttribute
-            } else {
-                SysfsError::from(e)
-            }
+        .map_err(SysfsError::from_io)
+}
+
+/// Lists the names of the entries in a *sysfs* directory, sorted. This is
+/// how devices of a class or bus are discovered.
+///
+/// If the directory does not exist, [`SysfsError::MissingAttribute`] is
+/// returned, since that usually means the subsystem is unavailable.
+pub fn sysfs_list(dir_path: &str) -> Result<Vec<String>> {
+    let mut names = std::fs::read_dir(dir_path)
+        .map_err(SysfsError::from_io)?
+        .map(|res| {
+            res.map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .map_err(SysfsError::from_io)
         })
+        .collect::<Result<Vec<_>>>()?;
+    names.sort_unstable();
+    Ok(names)
+}
+
+/// Lists the numbers of the entries in a *sysfs* directory that are named
+/// `prefix` followed by a number, such as `thermal_zone0` or `policy3`,
+/// sorted numerically.
+pub fn sysfs_list_indexed(dir_path: &str, prefix: &str) -> Result<Vec<usize>> {
+    let mut indices: Vec<usize> = sysfs_list(dir_path)?
+        .iter()
+        .filter_map(|name| name.strip_prefix(prefix)?.parse().ok())
+        .collect();
+    indices.sort_unstable();
+    Ok(indices)
 }
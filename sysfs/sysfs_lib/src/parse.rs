@@ -0,0 +1,246 @@
+//! Parsers for the formats of *sysfs* attributes that hold more than a
+//! single value.
+//!
+//! These are meant to be used from the `let try_read = ...` closure of a
+//! `#[sysfs]` function, for example:
+//!
+//! ```ignore
+//! #[sysfs]
+//! pub fn time_in_state(cpu: usize) -> Vec<(usize, u64)> {
+//!     let try_read = parse::pairs;
+//!     ..
+//! }
+//! ```
+//!
+//! Every parser returns a [`ParseError`] describing the part of the text
+//! that did not match, instead of panicking.
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::{Choice, Result};
+
+/// The text of an attribute did not match the expected format.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("expected {expected}, found `{found}`")]
+pub struct ParseError {
+    /// What the parser was looking for, such as `KEY=VALUE`.
+    pub expected: String,
+    /// The part of the text that did not match.
+    pub found: String,
+}
+
+impl ParseError {
+    pub fn new(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        Self {
+            expected: expected.into(),
+            found: found.into(),
+        }
+    }
+}
+
+/// Parses a single value with [`FromStr`].
+pub fn value<T: FromStr>(text: &str) -> Result<T> {
+    let text = text.trim();
+    text.parse()
+        .map_err(|_| ParseError::new(std::any::type_name::<T>(), text).into())
+}
+
+/// Parses a hexadecimal number, with or without a `0x` prefix, such as the
+/// IDs and classes of PCI and USB devices.
+pub fn hex(text: &str) -> Result<u32> {
+    let text = text.trim();
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    u32::from_str_radix(digits, 16)
+        .map_err(|_| ParseError::new("a hexadecimal number", text).into())
+}
+
+/// Parses a hexadecimal number like [`hex`], and checks that it fits in
+/// `T`, such as the 16-bit vendor IDs of PCI and USB devices.
+pub fn hex_as<T: TryFrom<u32>>(text: &str) -> Result<T> {
+    T::try_from(hex(text)?)
+        .map_err(|_| ParseError::new(std::any::type_name::<T>(), text.trim()).into())
+}
+
+/// Parses a whitespace separated list of values, such as
+/// `scaling_available_frequencies`.
+pub fn list<T: FromStr>(text: &str) -> Result<Vec<T>> {
+    text.split_whitespace().map(value).collect()
+}
+
+/// Parses lines of `KEY=VALUE`, such as `uevent` files.
+pub fn key_values(text: &str) -> Result<BTreeMap<String, String>> {
+    lines(text)
+        .map(|line| {
+            line.split_once('=')
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .ok_or_else(|| ParseError::new("KEY=VALUE", line).into())
+        })
+        .collect()
+}
+
+/// Parses lines of two whitespace separated values, such as the
+/// `frequency time` lines of `time_in_state`.
+pub fn pairs<K: FromStr, V: FromStr>(text: &str) -> Result<Vec<(K, V)>> {
+    lines(text)
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(key), Some(val), None) => Ok((value(key)?, value(val)?)),
+                _ => Err(ParseError::new("two values", line).into()),
+            }
+        })
+        .collect()
+}
+
+/// Parses lines of `LABEL: REST`, such as the `0: 500Mhz *` lines of the
+/// `amdgpu` DPM tables, into the parsed label and the trimmed rest.
+pub fn labeled_lines<K: FromStr>(text: &str) -> Result<Vec<(K, String)>> {
+    lines(text)
+        .map(|line| {
+            let (label, rest) = line
+                .split_once(':')
+                .ok_or_else(|| ParseError::new("LABEL: VALUE", line))?;
+            Ok((value(label)?, rest.trim().to_owned()))
+        })
+        .collect()
+}
+
+/// A table with labeled columns and rows, such as `trans_table`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table<L, V> {
+    pub columns: Vec<L>,
+    pub rows: Vec<(L, Vec<V>)>,
+}
+
+/// Parses a table with a header row of column labels after a `:`, followed
+/// by rows of `LABEL: VALUES`. Lines before the header row, such as the
+/// `From : To` caption of `trans_table`, are skipped.
+///
+/// ```text
+///    From  :    To
+///          :   3400000   2800000
+///   3400000:         0        10
+///   2800000:        12         0
+/// ```
+pub fn table<L: FromStr, V: FromStr>(text: &str) -> Result<Table<L, V>> {
+    let mut lines = lines(text).skip_while(|line| !line.starts_with(':'));
+    let header = lines
+        .next()
+        .ok_or_else(|| ParseError::new("a header row starting with `:`", text))?;
+    let columns = list(&header[1..])?;
+    let rows = lines
+        .map(|line| {
+            let (label, values) = line
+                .split_once(':')
+                .ok_or_else(|| ParseError::new("LABEL: VALUES", line))?;
+            let values: Vec<V> = list(values)?;
+            if values.len() != columns.len() {
+                return Err(ParseError::new(
+                    format!("{} values", columns.len()),
+                    values.len().to_string(),
+                )
+                .into());
+            }
+            Ok((value(label)?, values))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Table { columns, rows })
+}
+
+/// Parses a list where the selected entry is in brackets, such as
+/// `[none] mq-deadline kyber`.
+///
+/// A single entry without brackets is taken as selected, since the kernel
+/// prints attributes with only one possible value that way, such as the
+/// `scheduler` of a disk without I/O scheduler support.
+pub fn bracketed<T: FromStr>(text: &str) -> Result<Choice<T>> {
+    let mut selected = None;
+    let entries = text
+        .split_whitespace()
+        .enumerate()
+        .map(|(index, token)| match token.strip_prefix('[') {
+            Some(token) => {
+                let token = token
+                    .strip_suffix(']')
+                    .ok_or_else(|| ParseError::new("a closing `]`", token))?;
+                if selected.replace(index).is_some() {
+                    return Err(ParseError::new("a single selected entry", text).into());
+                }
+                value(token)
+            }
+            None => value(token),
+        })
+        .collect::<Result<Vec<T>>>()?;
+    let selected = match selected {
+        Some(selected) => selected,
+        None if entries.len() == 1 => 0,
+        None => return Err(ParseError::new("a selected `[entry]`", text).into()),
+    };
+    // The index is always in bounds, since it was found while enumerating.
+    Ok(Choice::new(entries, selected).unwrap())
+}
+
+/// The non-empty lines of `text`, trimmed.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SysfsError;
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(hex("0x0c0330").unwrap(), 0x0c0330);
+        assert_eq!(hex("046d").unwrap(), 0x046d);
+        assert!(hex("0xzz").is_err());
+        assert_eq!(hex_as::<u16>("0x8086").unwrap(), 0x8086);
+        assert!(hex_as::<u8>("0x100").is_err());
+    }
+
+    #[test]
+    fn parse_key_values() {
+        let uevent =
+            key_values("POWER_SUPPLY_NAME=BAT0\nPOWER_SUPPLY_STATUS=Discharging\n").unwrap();
+        assert_eq!(uevent["POWER_SUPPLY_STATUS"], "Discharging");
+        assert!(matches!(key_values("NAME"), Err(SysfsError::Parse(_))));
+    }
+
+    #[test]
+    fn parse_pairs() {
+        let time_in_state = pairs::<u64, u64>("3400000 120\n2800000 80").unwrap();
+        assert_eq!(time_in_state, [(3400000, 120), (2800000, 80)]);
+        assert!(pairs::<u64, u64>("3400000").is_err());
+    }
+
+    #[test]
+    fn parse_table() {
+        let text = "   From  :    To\n         :   3400000   2800000\n  3400000:         0        \
+                    10\n  2800000:        12         0\n";
+        let trans_table = table::<u64, u64>(text).unwrap();
+        assert_eq!(trans_table.columns, [3400000, 2800000]);
+        assert_eq!(trans_table.rows[1], (2800000, vec![12, 0]));
+    }
+
+    #[test]
+    fn parse_bracketed() {
+        let choice = bracketed::<String>("[none] mq-deadline kyber").unwrap();
+        assert_eq!(choice.available(), ["none", "mq-deadline", "kyber"]);
+        assert_eq!(choice.selected_index(), 0);
+        assert!(bracketed::<String>("none kyber").is_err());
+        // Disks without I/O scheduler support, such as loop devices.
+        let choice = bracketed::<String>("none").unwrap();
+        assert_eq!(choice.available(), ["none"]);
+        assert_eq!(choice.selected(), "none");
+        assert!(bracketed::<String>("[none] [kyber]").is_err());
+    }
+
+    #[test]
+    fn parse_error_is_structured() {
+        let Err(SysfsError::Parse(e)) = list::<u32>("1 two 3") else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(e.found, "two");
+    }
+}
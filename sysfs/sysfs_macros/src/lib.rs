@@ -8,8 +8,9 @@ use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{
     parse_macro_input, parse_quote, Attribute, Block, Error, Expr, ExprClosure, ExprLit, ExprRange,
-    Ident, Item, ItemFn, ItemMod, Lit, LitStr, Local, LocalInit, Meta, MetaList, MetaNameValue,
-    Pat, PatIdent, PatType, RangeLimits, ReturnType, Signature, Stmt, Token, Type, Visibility,
+    GenericArgument, Ident, Item, ItemFn, ItemMod, Lit, LitStr, Local, LocalInit, Meta, MetaList,
+    MetaNameValue, Pat, PatIdent, PatType, PathArguments, RangeLimits, ReturnType, Signature, Stmt,
+    Token, Type, TypePath, Visibility,
 };
 
 macro_rules! err {
@@ -53,6 +54,7 @@ pub fn sysfs_attrs(args: TokenStream1, item: TokenStream1) -> TokenStream1 {
 #[derive(Clone, Default)]
 struct SysfsAttrArgs {
     sysfs_dir: Option<LitStr>,
+    sysfs_file: Option<LitStr>,
 }
 
 #[derive(Clone)]
@@ -62,6 +64,7 @@ struct ItemSysfsAttrFn {
     sig: Signature,
     let_read: Option<Local>,
     let_write: Option<Local>,
+    #[allow(dead_code)]
     dots: Token![..],
     block: Box<Block>,
 }
@@ -158,6 +161,10 @@ impl Parse for SysfsAttrArgs {
             // as a comma-punctuated list.
             let _in_token = <Token![in]>::parse(input)?;
             let sysfs_dir = expr_require_lit_str(Expr::parse(input)?)?;
+            // Allow further arguments to follow the directory.
+            if input.peek(Token![,]) {
+                let _comma_token = <Token![,]>::parse(input)?;
+            }
             let mut args = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
             args.insert(0, parse_quote!(sysfs_dir = #sysfs_dir));
             Self::try_from(args)
@@ -173,22 +180,24 @@ impl TryFrom<Punctuated<Meta, Token![,]>> for SysfsAttrArgs {
     type Error = Error;
 
     fn try_from(args: Punctuated<Meta, Token![,]>) -> syn::Result<Self> {
-        let args_span = args.span();
-
         let mut sysfs_dir = None;
+        let mut sysfs_file = None;
 
         args.into_iter().try_for_each(|arg| match arg {
             Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("sysfs_dir") => {
                 Ok(sysfs_dir = Some(expr_require_lit_str(value)?))
             }
+            Meta::NameValue(MetaNameValue { path, value, .. }) if path.is_ident("sysfs_file") => {
+                Ok(sysfs_file = Some(expr_require_lit_str(value)?))
+            }
             _ => err!(arg, "unknown meta argument"),
         })?;
 
-        let sysfs_dir =
-            sysfs_dir.ok_or_else(|| Error::new(args_span, "argument `sysfs_dir` is required"))?;
-
+        // A missing `sysfs_dir` is filled in by an enclosing `sysfs_attrs`,
+        // or otherwise falls back to a `SYSFS_DIR` in scope.
         Ok(Self {
-            sysfs_dir: Some(sysfs_dir),
+            sysfs_dir,
+            sysfs_file,
         })
     }
 }
@@ -242,7 +251,8 @@ impl TryFrom<ItemFn> for ItemSysfsAttrFn {
     ) -> Result<Self, Self::Error> {
         // Expect a local `let read = #init`, where the init is expected to be a
         // function that infallibly transforms a string into the return type of
-        // this function.
+        // this function. Alternatively `let try_read = #init` may be used for a
+        // function returning a `::sysfs_lib::Result` of the return type.
         let let_read = block
             .stmts
             .iter()
@@ -251,7 +261,7 @@ impl TryFrom<ItemFn> for ItemSysfsAttrFn {
                     pat: Pat::Ident(PatIdent { ident, .. }),
                     init: Some(LocalInit { .. }),
                     ..
-                }) if ident == "read")
+                }) if ident == "read" || ident == "try_read")
             })
             .map(|index| match block.stmts.remove(index) {
                 Stmt::Local(local) => local,
@@ -276,6 +286,24 @@ impl TryFrom<ItemFn> for ItemSysfsAttrFn {
                 _ => unreachable!(),
             });
 
+        // An attribute returning a `Choice<T>` reads the bracketed list and
+        // writes the bare `T`, unless told otherwise.
+        let (let_read, let_write) = match choice_type(&sig.output) {
+            Some(ty) => (
+                let_read.or_else(|| {
+                    Some(local(parse_quote! {
+                        let try_read = ::sysfs_lib::parse::bracketed;
+                    }))
+                }),
+                let_write.or_else(|| {
+                    Some(local(parse_quote! {
+                        let write = |value: #ty| value.to_string();
+                    }))
+                }),
+            ),
+            None => (let_read, let_write),
+        };
+
         // The dots at the end of the function indicate "et cetera",
         // where the generated content will be put. It is not allowed to have
         // code after the `..`, but you may before.
@@ -306,6 +334,34 @@ impl TryFrom<ItemFn> for ItemSysfsAttrFn {
     }
 }
 
+/// Unwraps a statement that is known to be a `let`.
+fn local(stmt: Stmt) -> Local {
+    match stmt {
+        Stmt::Local(local) => local,
+        _ => unreachable!(),
+    }
+}
+
+/// Returns `T` if the return type is `Choice<T>`.
+fn choice_type(output: &ReturnType) -> Option<&Type> {
+    let ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let Type::Path(TypePath { qself: None, path }) = ty.as_ref() else {
+        return None;
+    };
+    let segment = path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(ty)) if segment.ident == "Choice" && args.args.len() == 1 => {
+            Some(ty)
+        }
+        _ => None,
+    }
+}
+
 //
 // Code related to generating tokens starts here.
 //
@@ -316,12 +372,18 @@ fn sysfs_attr(args: &SysfsAttrArgs, item: ItemSysfsAttrFn) -> syn::Result<TokenS
         if let (Some(sysfs_dir), None) = (&args.sysfs_dir, &getter.sysfs_dir) {
             getter.sysfs_dir = Some(sysfs_dir.clone())
         }
+        if let Some(sysfs_file) = &args.sysfs_file {
+            getter.sysfs_file = sysfs_file.value();
+        }
         tokens.extend(getter.to_token_stream());
     }
     if let Ok(mut setter) = SetterFunction::try_from(item.clone()) {
         if let (Some(sysfs_dir), None) = (&args.sysfs_dir, &setter.sysfs_dir) {
             setter.sysfs_dir = Some(sysfs_dir.clone())
         }
+        if let Some(sysfs_file) = &args.sysfs_file {
+            setter.sysfs_file = sysfs_file.value();
+        }
         tokens.extend(setter.to_token_stream());
     }
     Ok(tokens)
@@ -329,11 +391,17 @@ fn sysfs_attr(args: &SysfsAttrArgs, item: ItemSysfsAttrFn) -> syn::Result<TokenS
 
 impl ToTokens for SysfsAttrArgs {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let Self { sysfs_dir } = self;
+        let Self {
+            sysfs_dir,
+            sysfs_file,
+        } = self;
         let mut args = Punctuated::<Meta, Token![,]>::new();
         if let Some(sysfs_dir) = sysfs_dir {
             args.push(parse_quote!(sysfs_dir = #sysfs_dir));
         }
+        if let Some(sysfs_file) = sysfs_file {
+            args.push(parse_quote!(sysfs_file = #sysfs_file));
+        }
         args.to_tokens(tokens)
     }
 }
@@ -355,6 +423,7 @@ impl TryFrom<SysfsModArgs> for SysfsAttrArgs {
         // but this may eventually be a fallible operation.
         Ok(Self {
             sysfs_dir: Some(other.sysfs_dir),
+            sysfs_file: None,
         })
     }
 }
@@ -365,6 +434,7 @@ struct GetterFunction {
     sig: Signature,
     into_type: Box<Type>,
     let_read: Local,
+    fallible: bool,
     stmts: Vec<Stmt>,
     sysfs_dir: Option<LitStr>,
     sysfs_file: String,
@@ -376,6 +446,7 @@ struct SetterFunction {
     sig: Signature,
     let_write: Local,
     from_ident: Ident,
+    #[allow(dead_code)]
     from_type: Box<Type>,
     stmts: Vec<Stmt>,
     sysfs_dir: Option<LitStr>,
@@ -399,12 +470,18 @@ impl ToTokens for GetterFunction {
             vis,
             sig,
             let_read,
+            fallible,
             into_type,
             stmts,
             sysfs_dir,
             sysfs_file,
         } = self;
         let let_sysfs_path = let_sysfs_path(sysfs_dir, sysfs_file);
+        let read = if *fallible {
+            quote!(::sysfs_lib::sysfs_try_read::<#into_type>(&sysfs_path, try_read))
+        } else {
+            quote!(::sysfs_lib::sysfs_read::<#into_type>(&sysfs_path, read))
+        };
 
         tokens.extend(quote! {
             #(#attrs)*
@@ -413,7 +490,7 @@ impl ToTokens for GetterFunction {
                 #let_sysfs_path
                 #let_read
                 unsafe {
-                    ::sysfs_lib::sysfs_read::<#into_type>(&sysfs_path, read)
+                    #read
                 }
             }
         });
@@ -464,6 +541,8 @@ impl TryFrom<ItemSysfsAttrFn> for GetterFunction {
     ) -> syn::Result<Self> {
         if let Some(mut local) = let_read {
             let sysfs_file = sig.ident.to_string();
+            let fallible =
+                matches!(&local.pat, Pat::Ident(PatIdent { ident, .. }) if ident == "try_read");
 
             // Take all attributes from the local, and apply them to the function
             // instead. The local assignment will not retain attributes.
@@ -486,12 +565,16 @@ impl TryFrom<ItemSysfsAttrFn> for GetterFunction {
                 sig,
                 into_type,
                 let_read: local,
+                fallible,
                 stmts: block.stmts,
                 sysfs_dir: None,
                 sysfs_file,
             })
         } else {
-            err!(block, "expected to find `let read = ...`")
+            err!(
+                block,
+                "expected to find `let read = ...` or `let try_read = ...`"
+            )
         }
     }
 }
@@ -574,4 +657,77 @@ mod tests {
             }
         }};
     }
+
+    #[test]
+    fn parse_getter_and_setter() {
+        test_parse!({
+            pub fn scaling_max_freq(cpu: usize) -> usize {
+                let read = |text: &str| text.parse().unwrap();
+                let write = |freq: usize| format!("{freq}");
+                ..
+            }
+        } => ItemSysfsAttrFn);
+    }
+
+    #[test]
+    fn parse_fallible_getter() {
+        let item: ItemSysfsAttrFn = parse_quote! {
+            pub fn time_in_state(cpu: usize) -> Vec<(usize, u64)> {
+                let try_read = ::sysfs_lib::parse::pairs;
+                ..
+            }
+        };
+        let getter = GetterFunction::try_from(item).unwrap();
+        assert!(getter.fallible);
+        assert!(getter
+            .to_token_stream()
+            .to_string()
+            .contains("sysfs_try_read"));
+    }
+
+    #[test]
+    fn parse_choice_defaults() {
+        let item: ItemSysfsAttrFn = parse_quote! {
+            pub fn scheduler(device: &str) -> Choice<String> {
+                ..
+            }
+        };
+        let getter = GetterFunction::try_from(item.clone()).unwrap();
+        assert!(getter.fallible);
+        let setter = SetterFunction::try_from(item).unwrap();
+        assert_eq!(setter.from_ident, "value");
+        assert_eq!(setter.sig.ident, "set_scheduler");
+    }
+
+    #[test]
+    fn parse_mod_args() {
+        test_parse!({ in "/sys/devices/system/cpu/cpu{cpu}" } => SysfsModArgs);
+        test_parse!({ sysfs_dir = "/sys/devices/system/cpu/cpu{cpu}" } => SysfsModArgs);
+    }
+
+    #[test]
+    fn mod_args_prefix_relative_dir() {
+        let args: SysfsModArgs = parse_quote!(in "/sys/devices/system/cpu");
+        let mut attr_args: SysfsAttrArgs = parse_quote!(in "./cpufreq");
+        args.update_attr_args(&mut attr_args).unwrap();
+        assert_eq!(
+            attr_args.sysfs_dir.unwrap().value(),
+            "/sys/devices/system/cpu/cpufreq"
+        );
+    }
+
+    #[test]
+    fn attr_args_keep_sysfs_file() {
+        let args: SysfsModArgs = parse_quote!(in "/sys/devices/system/cpu");
+        let mut attr_args: SysfsAttrArgs = parse_quote!(in "./cpu{cpu}", sysfs_file = "online");
+        args.update_attr_args(&mut attr_args).unwrap();
+        assert_eq!(
+            attr_args.sysfs_dir.unwrap().value(),
+            "/sys/devices/system/cpu/cpu{cpu}"
+        );
+        assert_eq!(attr_args.sysfs_file.unwrap().value(), "online");
+
+        let attr_args: SysfsAttrArgs = parse_quote!(sysfs_file = "online");
+        assert!(attr_args.sysfs_dir.is_none());
+    }
 }